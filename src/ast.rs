@@ -1,4 +1,8 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     // Number literal from dots/commas
     Number(i64),
@@ -8,29 +12,243 @@ pub enum Expr {
     Add(Box<Expr>, Box<Expr>),
     Sub(Box<Expr>, Box<Expr>),
     Mul(Box<Expr>, Box<Expr>),
+    Mod(Box<Expr>, Box<Expr>),
+    // Power with a constant, non-negative exponent
+    Pow(Box<Expr>, u32),
+    // Negation: -어
+    Neg(Box<Expr>),
+    // Comparisons, evaluating to 1 when they hold and 0 otherwise
+    Eq(Box<Expr>, Box<Expr>),
+    Lt(Box<Expr>, Box<Expr>),
+    Gt(Box<Expr>, Box<Expr>),
+    // Read an integer from stdin, like 엄식?: 식?
+    InputNum,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     // Assign to variable: 엄.. => vars[0] = 2
     Assign { var_index: usize, value: Expr },
     // Input: 엄식?
     Input { var_index: usize },
+    // Input `count` integers into var_index, var_index + 1, ...: 엄식?...
+    InputMany { var_index: usize, count: usize },
+    // Input one raw byte (-1 at end of input): 엄식ㅋ?
+    InputChar { var_index: usize },
     // Print number: 식..!
     PrintNum(Expr),
+    // Print number in base 2..=16 with lowercase digits: 식..!@16
+    PrintNumRadix { expr: Expr, radix: u32 },
     // Print char: 식.........ㅋ
     PrintChar(Expr),
     // Print newline: 식ㅋ
     PrintNewline,
-    // Conditional: 동탄{expr}?{stmt}
-    Conditional { condition: Expr, body: Vec<Statement> },
-    // Goto: 준..
-    Goto(usize),
+    // Print a string's UTF-8 bytes: 식"Hi\n"
+    PrintStr(String),
+    // Conditional: 동탄{expr}?{stmt} or 동탄{expr}?{stmt}:{else stmt}
+    Conditional {
+        condition: Expr,
+        body: Vec<Statement>,
+        else_body: Vec<Statement>,
+    },
+    // Loop: 동탄{expr}??{stmt}, or a block of lines up to a ';' on `end_line`.
+    // Each body statement keeps its position, since loops are lowered to
+    // conditionals and gotos between those lines (see `lower::lower_loops`).
+    While {
+        condition: Expr,
+        body: Vec<(Statement, Span)>,
+        end_line: usize,
+    },
+    // Goto: 준.. (target line, and the source line of the goto itself)
+    Goto { target: usize, line: usize },
+    // Goto with a target computed at runtime: 준어
+    GotoDynamic(Expr),
     // Return/Exit: 화이팅!..
     Return(Expr),
+    // Evaluate an expression for its side effects and discard the value: 식? 어
+    Eval(Expr),
+}
+
+// Source position of a statement's first token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
 }
 
-#[derive(Debug)]
+impl Span {
+    // The same position moved to `line`; the column only carries over on the same line
+    pub(crate) fn at_line(self, line: usize) -> Span {
+        if line == self.line { self } else { Span { line, col: 1 } }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Program {
     pub statements: Vec<(Statement, usize)>, // (statement, line_number)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spans: Vec<Span>, // Parallel to `statements`
+    /// Block-local variables declared with `$`, from the fresh index the parser
+    /// gave each one to the index written in the source.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub locals: BTreeMap<usize, usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub missing_end_marker: Option<usize>, // Last line, if the source ends before 이 사람이름이냐ㅋㅋ
+}
+
+impl Expr {
+    // Binding strength used to decide where parentheses are needed
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Eq(..) | Expr::Lt(..) | Expr::Gt(..) => 0,
+            Expr::Add(..) | Expr::Sub(..) => 1,
+            Expr::Mul(..) | Expr::Mod(..) => 2,
+            Expr::Pow(..) | Expr::Neg(_) => 3,
+            Expr::Number(_) | Expr::Var(_) | Expr::InputNum => 4,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parent: u8, right: bool) -> fmt::Result {
+        let prec = self.precedence();
+        if prec < parent || (right && prec == parent) {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+
+    /// Whether evaluating the expression reads stdin, so it must run even when
+    /// its value isn't needed.
+    pub fn reads_input(&self) -> bool {
+        match self {
+            Expr::InputNum => true,
+            Expr::Number(_) | Expr::Var(_) => false,
+            Expr::Add(l, r)
+            | Expr::Sub(l, r)
+            | Expr::Mul(l, r)
+            | Expr::Mod(l, r)
+            | Expr::Eq(l, r)
+            | Expr::Lt(l, r)
+            | Expr::Gt(l, r) => l.reads_input() || r.reads_input(),
+            Expr::Pow(operand, _) | Expr::Neg(operand) => operand.reads_input(),
+        }
+    }
+
+    fn fmt_binary(f: &mut fmt::Formatter<'_>, op: &str, prec: u8, left: &Expr, right: &Expr) -> fmt::Result {
+        left.fmt_operand(f, prec, false)?;
+        write!(f, " {} ", op)?;
+        right.fmt_operand(f, prec, true)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prec = self.precedence();
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Var(index) => write!(f, "var{}", index),
+            Expr::InputNum => write!(f, "input()"),
+            Expr::Add(l, r) => Self::fmt_binary(f, "+", prec, l, r),
+            Expr::Sub(l, r) => Self::fmt_binary(f, "-", prec, l, r),
+            Expr::Mul(l, r) => Self::fmt_binary(f, "*", prec, l, r),
+            Expr::Mod(l, r) => Self::fmt_binary(f, "%", prec, l, r),
+            Expr::Eq(l, r) => Self::fmt_binary(f, "=", prec, l, r),
+            Expr::Lt(l, r) => Self::fmt_binary(f, "<", prec, l, r),
+            Expr::Gt(l, r) => Self::fmt_binary(f, ">", prec, l, r),
+            Expr::Neg(operand) => {
+                write!(f, "-")?;
+                operand.fmt_operand(f, prec, true)
+            },
+            Expr::Pow(base, exp) => {
+                // Nested powers are parenthesized since ^ chains aren't parsed
+                base.fmt_operand(f, prec, true)?;
+                write!(f, " ^ {}", exp)
+            },
+        }
+    }
+}
+
+fn fmt_block<'a>(f: &mut fmt::Formatter<'_>, statements: impl IntoIterator<Item = &'a Statement>) -> fmt::Result {
+    write!(f, "{{")?;
+    for (i, stmt) in statements.into_iter().enumerate() {
+        write!(f, "{}{}", if i == 0 { " " } else { "; " }, stmt)?;
+    }
+    write!(f, " }}")
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Assign { var_index, value } => write!(f, "var{} = {}", var_index, value),
+            Statement::Input { var_index } => write!(f, "var{} = input()", var_index),
+            Statement::InputMany { var_index, count } => {
+                write!(f, "var{}..var{} = input()", var_index, var_index + count - 1)
+            },
+            Statement::InputChar { var_index } => write!(f, "var{} = input_char()", var_index),
+            Statement::PrintNum(expr) => write!(f, "print_num({})", expr),
+            Statement::PrintNumRadix { expr, radix } => write!(f, "print_num({}, base {})", expr, radix),
+            Statement::PrintChar(expr) => write!(f, "print_char({})", expr),
+            Statement::PrintNewline => write!(f, "print_newline()"),
+            Statement::PrintStr(text) => write!(f, "print_str({:?})", text),
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                write!(f, "if {} == 0 ", condition)?;
+                fmt_block(f, body)?;
+                if !else_body.is_empty() {
+                    write!(f, " else ")?;
+                    fmt_block(f, else_body)?;
+                }
+                Ok(())
+            },
+            Statement::While { condition, body, .. } => {
+                write!(f, "while {} == 0 ", condition)?;
+                fmt_block(f, body.iter().map(|(s, _)| s))
+            },
+            Statement::Goto { target, .. } => write!(f, "goto line {}", target),
+            Statement::GotoDynamic(expr) => write!(f, "goto line ({})", expr),
+            Statement::Return(expr) => write!(f, "return {}", expr),
+            Statement::Eval(expr) => write!(f, "eval({})", expr),
+        }
+    }
+}
+
+impl Program {
+    /// Position of the first token of statement `idx`. Programs built without
+    /// spans fall back to column 1 of the statement's line.
+    pub fn span(&self, idx: usize) -> Span {
+        self.spans.get(idx).copied().unwrap_or(Span {
+            line: self.statements[idx].1,
+            col: 1,
+        })
+    }
+
+    /// Source line of statement `idx`, the same as `statements[idx].1`.
+    pub fn line(&self, idx: usize) -> usize {
+        self.statements[idx].1
+    }
+
+    /// Maps each source line that has statements to the index of its first
+    /// statement, which is where a goto to that line resumes.
+    pub fn line_map(&self) -> HashMap<usize, usize> {
+        let mut map = HashMap::new();
+        for (idx, (_, line)) in self.statements.iter().enumerate() {
+            map.entry(*line).or_insert(idx);
+        }
+        map
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (stmt, line) in &self.statements {
+            writeln!(f, "{:>4}: {}", line, stmt)?;
+        }
+        Ok(())
+    }
 }
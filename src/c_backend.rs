@@ -0,0 +1,264 @@
+use crate::ast::{Expr, Program, Statement};
+use crate::codegen::CodeGenerator;
+use crate::error::CompileError;
+use crate::lower::lower_loops;
+use crate::visit::Visitor;
+use std::collections::BTreeSet;
+
+// Arithmetic goes through unsigned helpers so overflow wraps like the other
+// backends instead of being undefined behavior. They're inline so programs that
+// don't use some of them compile without warnings.
+const PRELUDE: &str = r#"#include <stdint.h>
+#include <stdio.h>
+
+static inline int64_t um_add(int64_t a, int64_t b) { return (int64_t)((uint64_t)a + (uint64_t)b); }
+static inline int64_t um_sub(int64_t a, int64_t b) { return (int64_t)((uint64_t)a - (uint64_t)b); }
+static inline int64_t um_mul(int64_t a, int64_t b) { return (int64_t)((uint64_t)a * (uint64_t)b); }
+static inline int64_t um_mod(int64_t a, int64_t b) { return b == -1 ? 0 : a % b; }
+static inline int64_t um_pow(int64_t a, uint32_t n) {
+    int64_t result = 1;
+    while (n-- > 0) {
+        result = um_mul(result, a);
+    }
+    return result;
+}
+
+/* Skip spaces/newlines, then accumulate decimal digits until the first non-digit */
+static inline int64_t um_read_int(void) {
+    int c = getchar();
+    while (c == ' ' || c == '\n') {
+        c = getchar();
+    }
+    int64_t acc = 0;
+    while (c >= '0' && c <= '9') {
+        acc = um_add(um_mul(acc, 10), c - '0');
+        c = getchar();
+    }
+    return acc;
+}
+
+/* Sign, then lowercase digits of the non-positive magnitude (which can hold INT64_MIN).
+ * The leading digits come from value / radix, whose place values always fit. */
+static inline void um_print_radix(int64_t value, int64_t radix) {
+    if (value < 0) {
+        putchar('-');
+    } else {
+        value = -value;
+    }
+    int64_t rest = value / radix;
+    if (rest < 0) {
+        int64_t place = 1;
+        while (rest / place <= -radix) {
+            place *= radix;
+        }
+        for (; place > 0; place /= radix) {
+            putchar("0123456789abcdef"[-(rest / place % radix)]);
+        }
+    }
+    putchar("0123456789abcdef"[-(value % radix)]);
+    putchar('\n');
+}
+"#;
+
+/// Lowers a program to a standalone C source file.
+///
+/// Variables become `int64_t` locals of `main`, `식?`/`식!`/`식ㅋ` map to
+/// stdio, and every line that a goto can reach gets a `line_N` label.
+pub fn generate_c(program: &Program) -> Result<String, CompileError> {
+    let program = &*lower_loops(program);
+    let max_line = program.statements.iter().map(|(_, line)| *line).max().unwrap_or(1);
+    for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+        CodeGenerator::check_goto_targets(stmt, max_line, program.span(idx))?;
+    }
+
+    let mut generator = CGenerator {
+        output: String::new(),
+        indent: 1,
+        max_line,
+        inputs: 0,
+    };
+    generator.generate(program);
+    Ok(generator.output)
+}
+
+// Number of 식? expressions in a program
+#[derive(Default)]
+struct InputReadCounter {
+    count: usize,
+}
+
+impl Visitor for InputReadCounter {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::InputNum = expr {
+            self.count += 1;
+        }
+        self.walk_expr(expr);
+    }
+}
+
+struct CGenerator {
+    output: String,
+    indent: usize,
+    max_line: usize,
+    inputs: usize, // `input_N` locals holding the 식? reads made so far
+}
+
+impl CGenerator {
+    fn generate(&mut self, program: &Program) {
+        self.output.push_str(PRELUDE);
+        self.output.push_str("\nint main(void) {\n");
+        for var_idx in CodeGenerator::collect_used_variables(program) {
+            self.line(&format!("int64_t var_{} = 0;", var_idx));
+        }
+        let mut input_reads = InputReadCounter::default();
+        input_reads.visit_program(program);
+        for idx in 0..input_reads.count {
+            self.line(&format!("int64_t input_{} = 0;", idx));
+        }
+
+        // Only lines something jumps to need a label (unused labels draw warnings)
+        let mut targets = BTreeSet::new();
+        let mut has_dynamic_goto = false;
+        for (stmt, _) in &program.statements {
+            Self::collect_targets(stmt, &mut targets, &mut has_dynamic_goto);
+        }
+        if has_dynamic_goto {
+            targets.extend(1..=self.max_line);
+        }
+
+        // A label for an empty line sits on the next statement, where its goto resumes
+        let mut pending = targets.into_iter().peekable();
+        for (stmt, line) in &program.statements {
+            while let Some(target) = pending.next_if(|target| target <= line) {
+                self.output.push_str(&format!("line_{}:\n", target));
+            }
+            self.generate_statement(stmt);
+        }
+
+        self.line("return 0;");
+        self.output.push_str("}\n");
+    }
+
+    fn collect_targets(stmt: &Statement, targets: &mut BTreeSet<usize>, dynamic: &mut bool) {
+        match stmt {
+            Statement::Goto { target, .. } => {
+                targets.insert(*target);
+            },
+            Statement::GotoDynamic(_) => *dynamic = true,
+            Statement::Conditional { body, else_body, .. } => {
+                for s in body.iter().chain(else_body) {
+                    Self::collect_targets(s, targets, dynamic);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn line(&mut self, code: &str) {
+        self.output.push_str(&"    ".repeat(self.indent));
+        self.output.push_str(code);
+        self.output.push('\n');
+    }
+
+    fn generate_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Assign { var_index, value } => {
+                let value = self.expr(value);
+                self.line(&format!("var_{} = {};", var_index, value));
+            },
+            Statement::Input { var_index } => self.line(&format!("var_{} = um_read_int();", var_index)),
+            Statement::InputMany { var_index, count } => {
+                for var_index in *var_index..var_index + count {
+                    self.generate_statement(&Statement::Input { var_index });
+                }
+            },
+            Statement::InputChar { var_index } => self.line(&format!("var_{} = getchar();", var_index)),
+            Statement::PrintNum(expr) => {
+                let value = self.expr(expr);
+                self.line(&format!("printf(\"%lld\\n\", (long long){});", value));
+            },
+            Statement::PrintNumRadix { expr, radix } => {
+                let value = self.expr(expr);
+                self.line(&format!("um_print_radix({}, {});", value, radix));
+            },
+            Statement::PrintChar(expr) => {
+                let value = self.expr(expr);
+                self.line(&format!("putchar((int){});", value));
+            },
+            Statement::PrintNewline => self.line("putchar('\\n');"),
+            Statement::PrintStr(text) => {
+                let bytes = text.bytes().map(|byte| format!("{:03o}", byte)).collect::<Vec<_>>().join("\\");
+                if !text.is_empty() {
+                    self.line(&format!("fputs(\"\\{}\", stdout);", bytes));
+                }
+            },
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                // Body runs when the condition is zero
+                let condition = self.expr(condition);
+                self.line(&format!("if ({} == 0) {{", condition));
+                self.indent += 1;
+                for s in body {
+                    self.generate_statement(s);
+                }
+                self.indent -= 1;
+                if !else_body.is_empty() {
+                    self.line("} else {");
+                    self.indent += 1;
+                    for s in else_body {
+                        self.generate_statement(s);
+                    }
+                    self.indent -= 1;
+                }
+                self.line("}");
+            },
+            Statement::While { .. } => unreachable!("loops are lowered before codegen"),
+            Statement::Goto { target, .. } => self.line(&format!("goto line_{};", target)),
+            Statement::GotoDynamic(expr) => {
+                // Unknown targets end the program like the Lamina backend
+                let target = self.expr(expr);
+                self.line(&format!("switch ({}) {{", target));
+                for line in 1..=self.max_line {
+                    self.line(&format!("case {}: goto line_{};", line, line));
+                }
+                self.line("default: return 0;");
+                self.line("}");
+            },
+            Statement::Return(expr) => {
+                let value = self.expr(expr);
+                self.line(&format!("return (int){};", value));
+            },
+            Statement::Eval(expr) => {
+                let value = self.expr(expr);
+                self.line(&format!("(void)({});", value));
+            },
+        }
+    }
+
+    // C leaves the order of function arguments unspecified, so each 식? is read
+    // into its own `input_N` local by a statement of its own first, in source order
+    fn expr(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Number(n) => format!("INT64_C({})", n),
+            Expr::Var(index) => format!("var_{}", index),
+            Expr::InputNum => {
+                let input = format!("input_{}", self.inputs);
+                self.inputs += 1;
+                self.line(&format!("{} = um_read_int();", input));
+                input
+            },
+            Expr::Add(left, right) => format!("um_add({}, {})", self.expr(left), self.expr(right)),
+            Expr::Sub(left, right) => format!("um_sub({}, {})", self.expr(left), self.expr(right)),
+            Expr::Mul(left, right) => format!("um_mul({}, {})", self.expr(left), self.expr(right)),
+            Expr::Mod(left, right) => format!("um_mod({}, {})", self.expr(left), self.expr(right)),
+            Expr::Pow(base, exp) => format!("um_pow({}, {})", self.expr(base), exp),
+            Expr::Neg(operand) => format!("um_sub(0, {})", self.expr(operand)),
+            Expr::Eq(left, right) => format!("(int64_t)({} == {})", self.expr(left), self.expr(right)),
+            Expr::Lt(left, right) => format!("(int64_t)({} < {})", self.expr(left), self.expr(right)),
+            Expr::Gt(left, right) => format!("(int64_t)({} > {})", self.expr(left), self.expr(right)),
+        }
+    }
+}
@@ -1,55 +1,431 @@
-use crate::ast::{Expr, Program, Statement};
-use std::collections::HashMap;
+use crate::ast::{Expr, Program, Span, Statement};
+use crate::diff::unified_diff;
+use crate::error::CompileError;
+use crate::lexer::normalize_line_endings;
+use crate::lint::unreachable_statements;
+use crate::lower::lower_loops;
+use crate::visit::Visitor;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 
 pub struct CodeGenerator {
     output: String,
     var_counter: usize,
     block_counter: usize,
+    max_line: usize,
     var_ptrs: HashMap<usize, String>, // Track variable pointers (var_index -> ptr_name)
+    var_regs: HashMap<usize, String>, // Variables kept in SSA temps (var_index -> current value)
+    block_consts: HashMap<i64, String>, // Constants already materialized in the current block
+    checked: bool,                      // Trap on signed overflow instead of wrapping
+    portable_print: bool,               // Print numbers digit by digit with writebyte instead of `print`
+    truthy_conditionals: bool,          // Run a conditional's body when its expression is nonzero
+    max_lines: Option<u64>,             // Abort once this many statements have run
+    with_metadata: bool,                // Precede `@main` with a metadata comment and annotation
+    opt_level: OptLevel,                // Which optimization passes run
+    needs_overflow_trap: bool,
+    source_lines: Option<Vec<String>>, // Source text for the per-line comments, if requested
+    newline_const: Option<String>,     // Function-wide 10, materialized in the entry block
+    jump_threads: HashMap<usize, usize>, // Goto target line -> final line after chasing goto-only lines
+    current_span: Span,                  // Position of the top-level statement being generated
+}
+
+/// Which optimization passes `CodeGenerator` runs. Each level includes the
+/// passes of the levels below it.
+///
+/// - `O0`: none. Every expression is computed as written, every constant gets
+///   its own temp, every empty line gets a label and every variable its own
+///   stack slot.
+/// - `O1`: constant folding, reuse of a constant already materialized in the
+///   same block (and one shared newline constant), and labels on empty lines
+///   only when a goto can land on them.
+/// - `O2`: jump threading through goto-only lines, SSA temps instead of stack
+///   slots for variables of goto-free code (mem2reg), and stack slots shared
+///   by variables whose lifetimes don't overlap.
+///
+/// Unreachable statements are dropped at every level, and block-local
+/// variables (declared with `$`) of different top-level statements share
+/// stack slots at every level, since none outlives its statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptLevel {
+    #[default]
+    O0,
+    O1,
+    O2,
+}
+
+// Every variable a program assigns or reads, in index order
+#[derive(Default)]
+struct VarCollector {
+    vars: BTreeSet<usize>,
+}
+
+impl Visitor for VarCollector {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        if let Statement::Assign { var_index, .. }
+        | Statement::Input { var_index }
+        | Statement::InputChar { var_index } = stmt
+        {
+            self.vars.insert(*var_index);
+        }
+        if let Statement::InputMany { var_index, count } = stmt {
+            self.vars.extend(*var_index..var_index + count);
+        }
+        self.walk_statement(stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Var(index) = expr {
+            self.vars.insert(*index);
+        }
+        self.walk_expr(expr);
+    }
+}
+
+// Whether a program prints a newline, via 식ㅋ or a constant 10 passed to 식...ㅋ
+#[derive(Default)]
+struct NewlinePrintFinder {
+    found: bool,
+}
+
+impl Visitor for NewlinePrintFinder {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::PrintNewline => self.found = true,
+            Statement::PrintStr(text) if text.contains('\n') => self.found = true,
+            Statement::PrintChar(expr) if matches!(CodeGenerator::fold_constants(expr, false), Expr::Number(10)) => {
+                self.found = true
+            },
+            _ => self.walk_statement(stmt),
+        }
+    }
+}
+
+// Whether a program runs the integer reader, and whether it writes numbers
+// digit by digit: every `식...!@N`, and every `식...!` with portable printing
+#[derive(Default)]
+struct ScratchUseFinder {
+    portable_print: bool,
+    reads_int: bool,
+    prints_digits: bool,
+}
+
+impl Visitor for ScratchUseFinder {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Input { .. } | Statement::InputMany { .. } => self.reads_int = true,
+            Statement::PrintNumRadix { .. } => self.prints_digits = true,
+            Statement::PrintNum(_) if self.portable_print => self.prints_digits = true,
+            _ => {},
+        }
+        self.walk_statement(stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::InputNum = expr {
+            self.reads_int = true;
+        }
+        self.walk_expr(expr);
+    }
+}
+
+/// Collects `CodeGenerator` options before constructing it, so new options can
+/// be added without changing `CodeGenerator::new`.
+#[derive(Default, Clone)]
+pub struct CodeGeneratorBuilder {
+    checked: bool,
+    portable_print: bool,
+    truthy_conditionals: bool,
+    max_lines: Option<u64>,
+    with_metadata: bool,
+    opt_level: OptLevel,
+    source: Option<String>,
+}
+
+impl CodeGeneratorBuilder {
+    /// Traps on signed overflow instead of wrapping; see `CodeGenerator::new`.
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Lowers `식...!` to a sign and decimal digits written with `writebyte`,
+    /// for targets whose `print` is missing or formats negatives differently.
+    pub fn portable_print(mut self, portable_print: bool) -> Self {
+        self.portable_print = portable_print;
+        self
+    }
+
+    /// Runs a `동탄` body when its expression is nonzero instead of zero, and
+    /// the else part when it is zero.
+    pub fn truthy_conditionals(mut self, truthy_conditionals: bool) -> Self {
+        self.truthy_conditionals = truthy_conditionals;
+        self
+    }
+
+    /// Counts every statement as it runs and exits with status 1 once more than
+    /// `max_lines` have run, so runaway goto loops terminate. Jump threading is
+    /// off with a limit, since it would jump past goto-only lines uncounted.
+    pub fn max_lines(mut self, max_lines: Option<u64>) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    /// Writes a `# metadata: ...` comment before `@main` with the number of
+    /// stack slots, SSA-resident variables, statements and source lines, and
+    /// marks `@main` `@noinline`. Lamina accepts `@inline`, `@noinline`,
+    /// `@export`, `@noreturn` and `@cold` on a function and rejects any other
+    /// annotation, so only hints from that list may be added here.
+    pub fn with_metadata(mut self, with_metadata: bool) -> Self {
+        self.with_metadata = with_metadata;
+        self
+    }
+
+    /// Selects which optimization passes run; see `OptLevel`. Defaults to `O0`.
+    pub fn opt_level(mut self, opt_level: OptLevel) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Annotates line labels with source text and expression temps with their
+    /// expressions; see `CodeGenerator::with_source`.
+    pub fn source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Generates IR for `program` at `OptLevel::O0` and at the configured level,
+    /// and returns a unified diff from the first to the second (empty when
+    /// they match). Backs `umjunsik --opt-diff`.
+    pub fn opt_diff(self, program: &Program) -> Result<String, CompileError> {
+        let level = self.opt_level;
+        let optimized = self.clone().build().generate(program)?;
+        let naive = self.opt_level(OptLevel::O0).build().generate(program)?;
+        Ok(unified_diff(&naive, &optimized, "-O0", &format!("-{:?}", level)))
+    }
+
+    pub fn build(self) -> CodeGenerator {
+        let mut codegen = CodeGenerator::new(self.checked);
+        codegen.portable_print = self.portable_print;
+        codegen.truthy_conditionals = self.truthy_conditionals;
+        codegen.max_lines = self.max_lines;
+        codegen.with_metadata = self.with_metadata;
+        codegen.opt_level = self.opt_level;
+        match self.source {
+            Some(source) => codegen.with_source(&source),
+            None => codegen,
+        }
+    }
 }
 
 impl Default for CodeGenerator {
     fn default() -> Self {
-        Self::new()
+        Self::new(false)
     }
 }
 
 impl CodeGenerator {
-    pub fn new() -> Self {
+    /// Creates a generator. With `checked`, `Add`/`Sub`/`Mul` branch to an
+    /// overflow trap that prints a message and exits with status 1 instead of wrapping.
+    pub fn new(checked: bool) -> Self {
         CodeGenerator {
             output: String::new(),
             var_counter: 0,
             block_counter: 0,
+            max_line: 1,
             var_ptrs: HashMap::new(),
+            var_regs: HashMap::new(),
+            block_consts: HashMap::new(),
+            checked,
+            portable_print: false,
+            truthy_conditionals: false,
+            max_lines: None,
+            with_metadata: false,
+            opt_level: OptLevel::O0,
+            needs_overflow_trap: false,
+            source_lines: None,
+            newline_const: None,
+            jump_threads: HashMap::new(),
+            current_span: Span { line: 1, col: 1 },
         }
     }
 
-    pub fn generate(&mut self, program: &Program) -> Result<String, String> {
-        // First pass: collect all variables used in the program
-        let used_vars = self.collect_used_variables(program);
+    /// Starts a `CodeGeneratorBuilder` with every option off and `OptLevel::O0`.
+    pub fn builder() -> CodeGeneratorBuilder {
+        CodeGeneratorBuilder::default()
+    }
+
+    /// Annotates each `line_N` block with a `# source line N, col C: <text>` comment
+    /// taken from `source`, which should be the text the program was parsed from.
+    ///
+    /// The temp holding a statement's expression value, or a number read from
+    /// stdin, also gets a trailing `# = <expr>` comment, e.g. `# = var3 + 2`,
+    /// when the instruction that defines it is the expression's last.
+    pub fn with_source(mut self, source: &str) -> Self {
+        let source = normalize_line_endings(source);
+        self.source_lines = Some(source.lines().map(|line| line.trim().to_string()).collect());
+        self
+    }
+
+    pub fn generate(&mut self, program: &Program) -> Result<String, CompileError> {
+        let mut out = String::new();
+        self.generate_into(program, &mut out)?;
+        Ok(out)
+    }
+
+    /// Generates IR for `program` straight into `out`.
+    ///
+    /// IR is buffered one line label at a time and flushed to `out` as each line
+    /// is finished, so large programs never hold the whole module in memory twice.
+    pub fn generate_into<W: fmt::Write>(&mut self, program: &Program, out: &mut W) -> Result<(), CompileError> {
+        trace_span!("codegen");
+        let program = &*lower_loops(program);
 
         // Determine max line number to create labels for ALL lines
         let max_line = program.statements.iter()
             .map(|(_, line)| *line)
             .max()
             .unwrap_or(1);
+        self.max_line = max_line;
+
+        // Every line from 1 to max_line can be jumped to; reject gotos past the last one
+        for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+            Self::check_goto_targets(stmt, max_line, program.span(idx))?;
+        }
+
+        // Statements after a goto or return that nothing jumps to are dropped
+        // (lint warns about them). Whole lines only go once no goto can reach
+        // them, so the labels of the lines that remain are unaffected.
+        let unreachable = unreachable_statements(program);
+        let reachable_program;
+        let program = if unreachable.is_empty() {
+            program
+        } else {
+            reachable_program = Self::without_statements(program, &unreachable);
+            &reachable_program
+        };
+
+        // First pass: collect all variables used in the program
+        let used_vars = Self::collect_used_variables(program);
+
+        // Empty lines only get a label when something jumps to them
+        let mut goto_targets = std::collections::BTreeSet::new();
+        let mut has_dynamic_goto = false;
+        for (stmt, _) in &program.statements {
+            Self::collect_goto_targets(stmt, &mut goto_targets, &mut has_dynamic_goto);
+        }
 
-        // Create a map of line_number -> statement_index
-        let mut line_to_stmt: std::collections::HashMap<usize, usize> = std::collections::HashMap::new();
-        for (idx, (_, line_num)) in program.statements.iter().enumerate() {
-            line_to_stmt.insert(*line_num, idx);
+        // Threading would skip the goto-only lines the step counter has to count
+        self.jump_threads = if self.opt_level >= OptLevel::O2 && self.max_lines.is_none() {
+            Self::thread_jumps(program, &goto_targets)
+        } else {
+            HashMap::new()
+        };
+
+        // Without gotos every block dominates the code after it, so a variable's
+        // value can simply be threaded through SSA temps. Assignments inside a
+        // conditional would need a phi at the join, so those variables (and every
+        // variable, once there is a goto) stay in stack slots.
+        let mut branch_writes = BTreeSet::new();
+        let has_goto = program.statements.iter().any(|(stmt, _)| Self::contains_goto(stmt));
+        for (stmt, _) in &program.statements {
+            Self::collect_branch_writes(stmt, false, &mut branch_writes);
+        }
+        let mem2reg = self.opt_level >= OptLevel::O2 && !has_goto;
+        // A block-local variable is declared in its body, where straight-line
+        // code dominates every later read, gotos or not. Only a write from a
+        // conditional nested inside that body needs a phi.
+        let nested_local_writes = Self::nested_local_writes(program);
+        let (reg_vars, mem_vars): (Vec<usize>, Vec<usize>) = used_vars.into_iter().partition(|var| {
+            if program.locals.contains_key(var) {
+                self.opt_level >= OptLevel::O2 && !nested_local_writes.contains(var)
+            } else {
+                mem2reg && !branch_writes.contains(var)
+            }
+        });
+        let (local_mem_vars, mem_vars): (Vec<usize>, Vec<usize>) =
+            mem_vars.into_iter().partition(|var| program.locals.contains_key(var));
+
+        // Allocate only the variables that are actually used, letting variables
+        // with non-overlapping lifetimes share a slot. Slots are numbered densely
+        // in order of their owner's index, with the source variables in a comment.
+        let mut slot_owners = if self.opt_level >= OptLevel::O2 {
+            Self::assign_slots(program, &mem_vars)
+        } else {
+            mem_vars.iter().map(|&var| (var, var)).collect()
+        };
+        slot_owners.extend(Self::assign_local_slots(program, &local_mem_vars));
+        let mut slots: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &var_idx in mem_vars.iter().chain(&local_mem_vars) {
+            slots.entry(slot_owners[&var_idx]).or_default().push(var_idx);
         }
 
         // Generate main function
+        if self.with_metadata {
+            self.output.push_str(&format!(
+                "# metadata: stack_slots={} ssa_vars={} statements={} lines={}\n",
+                slots.len(),
+                reg_vars.len(),
+                program.statements.len(),
+                max_line
+            ));
+            self.output.push_str("@noinline\n");
+        }
         self.output.push_str("fn @main() -> i64 {\n");
         self.output.push_str("  entry:\n");
 
-        // Allocate only the variables that are actually used
-        for var_idx in used_vars {
-            let ptr = format!("%var_ptr_{}", var_idx);
-            self.output.push_str(&format!("    {} = alloc.ptr.stack i64\n", ptr));
+        self.var_regs.clear();
+        if !reg_vars.is_empty() {
+            self.output.push_str("    %zero_const = add.i64 0, 0\n");
+            for var_idx in reg_vars {
+                self.var_regs.insert(var_idx, "%zero_const".to_string());
+            }
+        }
+
+        // Lamina's x86_64 backend puts stack allocations below the frame it
+        // reserves, where calls overwrite them, so every slot (the reader's and
+        // printer's scratch too) is a heap cell allocated once, here. Nothing
+        // frees them; they last until the program exits, like main's frame.
+        for (slot, vars) in slots.values().enumerate() {
+            let ptr = format!("%var_ptr_{}", slot);
+            let names: Vec<String> = vars.iter().map(|var_idx| format!("var{}", var_idx)).collect();
+            self.output.push_str(&format!("    # {}: {}\n", ptr, names.join(", ")));
+            self.output.push_str(&format!("    {} = alloc.ptr.heap i64\n", ptr));
             self.output.push_str(&format!("    store.i64 {}, 0\n", ptr));
-            self.var_ptrs.insert(var_idx, ptr);
+            for &var_idx in vars {
+                self.var_ptrs.insert(var_idx, ptr.clone());
+            }
+        }
+
+        let mut scratch_uses = ScratchUseFinder {
+            portable_print: self.portable_print,
+            ..Default::default()
+        };
+        scratch_uses.visit_program(program);
+        if scratch_uses.reads_int {
+            for ptr in ["%input_acc_ptr", "%input_byte_ptr"] {
+                self.output.push_str(&format!("    {} = alloc.ptr.heap i64\n", ptr));
+            }
+        }
+        if scratch_uses.prints_digits {
+            for ptr in ["%print_mag_ptr", "%print_rest_ptr", "%print_pow_ptr"] {
+                self.output.push_str(&format!("    {} = alloc.ptr.heap i64\n", ptr));
+            }
+        }
+
+        // Programs that print newlines share one constant defined in the entry
+        // block, which dominates every other block
+        let mut newline_prints = NewlinePrintFinder::default();
+        newline_prints.visit_program(program);
+        self.newline_const = None;
+        if newline_prints.found && self.opt_level >= OptLevel::O1 {
+            self.output.push_str("    %nl_const = add.i64 10, 0\n");
+            self.newline_const = Some("%nl_const".to_string());
+        }
+
+        // Executed statement count for the line limit, kept in memory across gotos
+        if self.max_lines.is_some() {
+            self.output.push_str("    %steps_ptr = alloc.ptr.heap i64\n");
+            self.output.push_str("    store.i64 %steps_ptr, 0\n");
         }
 
         // Add jump to first line if we have statements
@@ -57,237 +433,393 @@ impl CodeGenerator {
             let first_line = program.statements[0].1;
             self.output.push_str(&format!("    jmp line_{}\n", first_line));
         }
+        self.flush_into(out)?;
 
+        let labels = Self::statement_labels(program);
         let mut last_needs_terminator = true;
         let mut current_line = 1;
 
         for (idx, (stmt, line_num)) in program.statements.iter().enumerate() {
-            // Add labels for all lines from current_line to line_num
-            while current_line <= *line_num {
-                self.output.push_str(&format!("\n  line_{}:\n", current_line));
-
-                // If this line has a statement, generate it
-                if current_line == *line_num {
-                    let needs_jump = self.generate_statement(stmt)?;
-                    last_needs_terminator = needs_jump;
-
-                    // Add fall-through jump to next statement's line if needed
-                    if needs_jump && idx + 1 < program.statements.len() {
-                        let next_line = program.statements[idx + 1].1;
-                        self.output.push_str(&format!("    jmp line_{}\n", next_line));
-                    }
-                } else {
-                    // Empty line - just jump to next line
-                    if current_line < max_line {
-                        self.output.push_str(&format!("    jmp line_{}\n", current_line + 1));
-                    }
+            // Empty lines before this statement only need a label when something jumps to them
+            while current_line < *line_num {
+                if has_dynamic_goto || goto_targets.contains(&current_line) || self.opt_level == OptLevel::O0 {
+                    // Forward straight to the next statement's line
+                    self.emit_label(&format!("line_{}", current_line));
+                    self.output.push_str(&format!("    jmp line_{}\n", line_num));
                 }
-
                 current_line += 1;
+                self.flush_into(out)?;
+            }
+            current_line = line_num + 1;
+
+            self.current_span = program.span(idx);
+            trace_span!("statement", line = *line_num, index = idx);
+            if let Some(text) = self.source_comment(&labels[idx], self.current_span) {
+                self.output.push_str(&text);
+            }
+            self.emit_label(&labels[idx]);
+            if let Some(max_lines) = self.max_lines {
+                self.generate_step_count(max_lines);
+            }
+            let needs_jump = self.generate_statement(stmt)?;
+            last_needs_terminator = needs_jump;
+
+            // Add fall-through jump to the next statement if needed
+            if needs_jump && idx + 1 < program.statements.len() {
+                self.output.push_str(&format!("    jmp {}\n", labels[idx + 1]));
             }
+            self.flush_into(out)?;
         }
 
         // Add default return only if last statement needs it
         if last_needs_terminator {
             self.output.push_str("    ret.i64 0\n");
         }
+        if self.needs_overflow_trap {
+            self.generate_trap("overflow_trap", b"arithmetic overflow\n");
+        }
+        if self.max_lines.is_some() && !program.statements.is_empty() {
+            self.generate_trap("line_limit_trap", b"line limit exceeded\n");
+        }
         self.output.push_str("}\n");
+        self.flush_into(out)
+    }
+
+    // Only the first statement of a line carries the comment; later `~` segments share it
+    fn source_comment(&self, label: &str, span: Span) -> Option<String> {
+        let source_lines = self.source_lines.as_ref()?;
+        if label != format!("line_{}", span.line) {
+            return None;
+        }
+        let text = source_lines.get(span.line - 1)?;
+        Some(format!("\n  # source line {}, col {}: {}", span.line, span.col, text))
+    }
 
-        Ok(self.output.clone())
+    // Move the buffered IR into the caller's writer
+    fn flush_into<W: fmt::Write>(&mut self, out: &mut W) -> Result<(), CompileError> {
+        out.write_str(&self.output).map_err(|_| CompileError::Codegen {
+            span: None,
+            message: "Failed to write generated IR".to_string(),
+        })?;
+        self.output.clear();
+        Ok(())
     }
 
-    fn collect_used_variables(&self, program: &Program) -> Vec<usize> {
-        use std::collections::BTreeSet;
-        let mut vars = BTreeSet::new();
+    // Label for each statement. The first statement on a line owns `line_N`, so a goto
+    // to that line resumes there; later `~`-separated statements get `line_N_2`, `line_N_3`, ...
+    fn statement_labels(program: &Program) -> Vec<String> {
+        let line_map = program.line_map();
+        program
+            .statements
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, line))| match idx - line_map[line] {
+                0 => format!("line_{}", line),
+                offset => format!("line_{}_{}", line, offset + 1),
+            })
+            .collect()
+    }
+
+    // `program` minus the statements at `indices`, keeping each span with its statement
+    fn without_statements(program: &Program, indices: &BTreeSet<usize>) -> Program {
+        let mut kept = Program {
+            statements: Vec::new(),
+            spans: Vec::new(),
+            locals: program.locals.clone(),
+            missing_end_marker: program.missing_end_marker,
+        };
+        for (idx, entry) in program.statements.iter().enumerate() {
+            if !indices.contains(&idx) {
+                kept.statements.push(entry.clone());
+                kept.spans.push(program.span(idx));
+            }
+        }
+        kept
+    }
+
+    // `span` is the position of the top-level statement containing `stmt`
+    pub(crate) fn check_goto_targets(stmt: &Statement, max_line: usize, span: Span) -> Result<(), CompileError> {
+        match stmt {
+            Statement::Goto { target, line } if *target == 0 || *target > max_line => Err(CompileError::Codegen {
+                span: Some(span.at_line(*line)),
+                message: format!("goto target line {} does not exist", target),
+            }),
+            Statement::Conditional { body, else_body, .. } => {
+                for s in body.iter().chain(else_body) {
+                    Self::check_goto_targets(s, max_line, span)?;
+                }
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
 
+    // Map each used variable to the variable whose stack slot it lives in.
+    //
+    // Without gotos, control only moves forward through the statement list, so a
+    // variable is live from its first to its last mentioning statement. A variable
+    // may take over a slot whose previous owner is dead, but only if its first
+    // statement overwrites it unconditionally; otherwise it could observe the old
+    // owner's value instead of 0. Any goto makes every variable live everywhere.
+    fn assign_slots(program: &Program, used_vars: &[usize]) -> HashMap<usize, usize> {
+        let mut owners: HashMap<usize, usize> = used_vars.iter().map(|&var| (var, var)).collect();
+        if program.statements.iter().any(|(stmt, _)| Self::contains_goto(stmt)) {
+            return owners;
+        }
+
+        let mut first = HashMap::new();
+        let mut last = HashMap::new();
+        for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+            let mut collector = VarCollector::default();
+            collector.visit_statement(stmt);
+            for var in collector.vars {
+                first.entry(var).or_insert(idx);
+                last.insert(var, idx);
+            }
+        }
+
+        let mut by_start = used_vars.to_vec();
+        by_start.sort_by_key(|var| first[var]);
+
+        // (owner, statement index where the slot's current occupant dies)
+        let mut slots: Vec<(usize, usize)> = Vec::new();
+        for var in by_start {
+            let start = first[&var];
+            let reusable = Self::overwrites_first(&program.statements[start].0, var)
+                .then(|| slots.iter_mut().find(|(_, end)| *end < start))
+                .flatten();
+            match reusable {
+                Some(slot) => {
+                    owners.insert(var, slot.0);
+                    slot.1 = last[&var];
+                },
+                None => slots.push((var, last[&var])),
+            }
+        }
+
+        owners
+    }
+
+    // Map each block-local variable that needs a stack slot to the variable whose
+    // slot it lives in. A block-local variable dies with the top-level statement
+    // that declares it and is written before it is read, so only the ones of one
+    // statement need distinct slots, at every optimization level.
+    fn assign_local_slots(program: &Program, locals: &[usize]) -> HashMap<usize, usize> {
+        let mut owners = HashMap::new();
+        let mut slots: Vec<usize> = Vec::new(); // Owner of each shared slot
         for (stmt, _) in &program.statements {
-            Self::collect_vars_from_statement(stmt, &mut vars);
+            let mut collector = VarCollector::default();
+            collector.visit_statement(stmt);
+            let vars: Vec<usize> = collector.vars.into_iter().filter(|var| locals.contains(var)).collect();
+
+            // Merged files number their locals alike, so one may already have a slot
+            let mut taken: BTreeSet<usize> = vars.iter().filter_map(|var| owners.get(var).copied()).collect();
+            for var in vars {
+                if owners.contains_key(&var) {
+                    continue;
+                }
+                let owner = match slots.iter().find(|owner| !taken.contains(*owner)) {
+                    Some(&owner) => owner,
+                    None => {
+                        slots.push(var);
+                        var
+                    },
+                };
+                taken.insert(owner);
+                owners.insert(var, owner);
+            }
         }
+        owners
+    }
 
-        vars.into_iter().collect()
+    // Block-local variables written inside a conditional nested in the body that
+    // declares them, found as writes at more than one 동탄 depth
+    fn nested_local_writes(program: &Program) -> BTreeSet<usize> {
+        fn walk(stmt: &Statement, depth: usize, depths: &mut HashMap<usize, BTreeSet<usize>>) {
+            match stmt {
+                Statement::Assign { var_index, .. }
+                | Statement::Input { var_index }
+                | Statement::InputChar { var_index } => {
+                    depths.entry(*var_index).or_default().insert(depth);
+                },
+                Statement::Conditional { body, else_body, .. } => {
+                    for s in body.iter().chain(else_body) {
+                        walk(s, depth + 1, depths);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        let mut depths = HashMap::new();
+        for (stmt, _) in &program.statements {
+            walk(stmt, 0, &mut depths);
+        }
+        depths
+            .into_iter()
+            .filter(|(var, depths)| program.locals.contains_key(var) && depths.len() > 1)
+            .map(|(var, _)| var)
+            .collect()
     }
 
-    fn collect_vars_from_statement(stmt: &Statement, vars: &mut std::collections::BTreeSet<usize>) {
+    fn contains_goto(stmt: &Statement) -> bool {
         match stmt {
-            Statement::Assign { var_index, value } => {
-                vars.insert(*var_index);
-                Self::collect_vars_from_expr(value, vars);
+            Statement::Goto { .. } | Statement::GotoDynamic(_) => true,
+            Statement::Conditional { body, else_body, .. } => {
+                body.iter().chain(else_body).any(Self::contains_goto)
             },
-            Statement::Input { var_index } => {
+            _ => false,
+        }
+    }
+
+    // Variables assigned (or read into) inside a conditional's body or else part
+    fn collect_branch_writes(stmt: &Statement, in_branch: bool, vars: &mut BTreeSet<usize>) {
+        match stmt {
+            Statement::Assign { var_index, .. }
+            | Statement::Input { var_index }
+            | Statement::InputChar { var_index }
+                if in_branch =>
+            {
                 vars.insert(*var_index);
             },
-            Statement::PrintNum(expr) | Statement::PrintChar(expr) => {
-                Self::collect_vars_from_expr(expr, vars);
+            Statement::InputMany { var_index, count } if in_branch => {
+                vars.extend(*var_index..var_index + count);
             },
-            Statement::PrintNewline => {},
-            Statement::Conditional { condition, body } => {
-                Self::collect_vars_from_expr(condition, vars);
-                for s in body {
-                    Self::collect_vars_from_statement(s, vars);
+            Statement::Conditional { body, else_body, .. } => {
+                for s in body.iter().chain(else_body) {
+                    Self::collect_branch_writes(s, true, vars);
                 }
             },
-            Statement::Goto(_) => {},
-            Statement::Return(expr) => {
-                Self::collect_vars_from_expr(expr, vars);
+            _ => {},
+        }
+    }
+
+    // Whether `stmt` writes `var` before anything could read it
+    fn overwrites_first(stmt: &Statement, var: usize) -> bool {
+        match stmt {
+            Statement::Assign { var_index, value } if *var_index == var => {
+                let mut reads = VarCollector::default();
+                reads.visit_expr(value);
+                !reads.vars.contains(&var)
             },
+            Statement::Input { var_index } | Statement::InputChar { var_index } => *var_index == var,
+            Statement::InputMany { var_index, count } => (*var_index..var_index + count).contains(&var),
+            _ => false,
         }
     }
 
-    fn collect_vars_from_expr(expr: &Expr, vars: &mut std::collections::BTreeSet<usize>) {
-        match expr {
-            Expr::Number(_) => {},
-            Expr::Var(index) => {
-                vars.insert(*index);
+    // For each goto target whose line starts with another goto, find where the
+    // chain finally lands so the first jump can go straight there. The chase is
+    // bounded by the statement count; a chain that never settles is a goto cycle
+    // and is left alone.
+    fn thread_jumps(program: &Program, goto_targets: &BTreeSet<usize>) -> HashMap<usize, usize> {
+        let statements = &program.statements;
+        let mut threads = HashMap::new();
+
+        for &start in goto_targets {
+            let mut current = start;
+            let mut hops = 0;
+            let settled = loop {
+                let landing = statements.iter().find(|(_, line)| *line >= current);
+                match landing {
+                    Some((Statement::Goto { target, .. }, _)) if *target != current => {
+                        if hops == statements.len() {
+                            break false;
+                        }
+                        current = *target;
+                        hops += 1;
+                    },
+                    _ => break true,
+                }
+            };
+            if settled && current != start {
+                threads.insert(start, current);
+            }
+        }
+
+        threads
+    }
+
+    fn collect_goto_targets(stmt: &Statement, targets: &mut std::collections::BTreeSet<usize>, dynamic: &mut bool) {
+        match stmt {
+            Statement::Goto { target, .. } => {
+                targets.insert(*target);
             },
-            Expr::Add(left, right) | Expr::Sub(left, right) | Expr::Mul(left, right) => {
-                Self::collect_vars_from_expr(left, vars);
-                Self::collect_vars_from_expr(right, vars);
+            Statement::GotoDynamic(_) => *dynamic = true,
+            Statement::Conditional { body, else_body, .. } => {
+                for s in body.iter().chain(else_body) {
+                    Self::collect_goto_targets(s, targets, dynamic);
+                }
             },
+            _ => {},
         }
     }
 
+    pub(crate) fn collect_used_variables(program: &Program) -> Vec<usize> {
+        let mut collector = VarCollector::default();
+        collector.visit_program(program);
+        collector.vars.into_iter().collect()
+    }
 
-    fn generate_statement(&mut self, stmt: &Statement) -> Result<bool, String> {
+    fn generate_statement(&mut self, stmt: &Statement) -> Result<bool, CompileError> {
+        trace_event!(
+            line = self.current_span.line,
+            col = self.current_span.col,
+            statement = %stmt,
+            "generate_statement"
+        );
         match stmt {
             Statement::Assign { var_index, value } => {
-                let expr_var = self.generate_expr(value)?;
-                // Store to memory location
-                if let Some(ptr) = self.var_ptrs.get(var_index).cloned() {
-                    self.output.push_str(&format!("    store.i64 {}, {}\n", ptr, expr_var));
-                } else {
-                    return Err(format!("Variable index {} out of range", var_index));
-                }
+                // Constants (including the 0 of a bare 엄) are stored as immediates
+                let expr_var = match self.fold(value) {
+                    Expr::Number(n) if !self.var_regs.contains_key(var_index) => self.operand(n),
+                    Expr::Number(n) => self.const_var(n),
+                    folded => {
+                        let expr_var = self.generate_expr(&folded)?;
+                        self.annotate(&expr_var, value);
+                        expr_var
+                    },
+                };
+                self.store_var(*var_index, expr_var);
                 Ok(true) // Needs fall-through jump
             },
             Statement::Input { var_index } => {
-                // Read an integer from stdin (multiple digits until newline/space)
-                // Use readint instruction if available, otherwise implement digit parsing
-
-                // For now, use a simple implementation:
-                // Call a runtime helper function that reads an integer
-                // Since Lamina doesn't have readint, we'll generate inline code
-
-                let skip_ws = format!("input_skip_ws_{}", self.block_counter);
-                let read_start = format!("input_start_{}", self.block_counter);
-                let read_loop = format!("input_loop_{}", self.block_counter);
-                let read_done = format!("input_done_{}", self.block_counter);
-                self.block_counter += 1;
-
-                // Allocate accumulator
-                let acc_ptr = self.new_var();
-                self.output.push_str(&format!("    {} = alloc.ptr.stack i64\n", acc_ptr));
-                self.output.push_str(&format!("    store.i64 {}, 0\n", acc_ptr));
-
-                // Allocate byte storage
-                let byte_ptr = self.new_var();
-                self.output.push_str(&format!("    {} = alloc.ptr.stack i64\n", byte_ptr));
-                self.output.push_str(&format!("    store.i64 {}, 0\n", byte_ptr));
-
-                self.output.push_str(&format!("    jmp {}\n", skip_ws));
-
-                // Skip whitespace
-                self.output.push_str(&format!("\n  {}:\n", skip_ws));
-                let ws_byte = self.new_var();
-                self.output.push_str(&format!("    {} = readbyte\n", ws_byte));
-                self.output.push_str(&format!("    store.i64 {}, {}\n", byte_ptr, ws_byte));
-
-                let space_val = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 32, 0\n", space_val));
-                let is_space = self.new_var();
-                self.output.push_str(&format!("    {} = eq.i64 {}, {}\n", is_space, ws_byte, space_val));
-
-                let newline_val = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 10, 0\n", newline_val));
-                let is_newline = self.new_var();
-                self.output.push_str(&format!("    {} = eq.i64 {}, {}\n", is_newline, ws_byte, newline_val));
-
-                // Check if is_space OR is_newline (a + b > 0 since they're booleans)
-                let is_ws = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 {}, {}\n", is_ws, is_space, is_newline));
-
-                self.output.push_str(&format!("    br {}, {}, {}\n", is_ws, skip_ws, read_start));
-
-                // Start reading number
-                self.output.push_str(&format!("\n  {}:\n", read_start));
-                self.output.push_str(&format!("    jmp {}\n", read_loop));
-
-                // Read loop
-                self.output.push_str(&format!("\n  {}:\n", read_loop));
-                let curr_byte = self.new_var();
-                self.output.push_str(&format!("    {} = load.i64 {}\n", curr_byte, byte_ptr));
-
-                // Check if digit (48-57)
-                // digit = curr_byte - 48, is_digit = (digit >= 0 && digit <= 9)
-                let ascii_zero = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 48, 0\n", ascii_zero));
-                let digit_val = self.new_var();
-                self.output.push_str(&format!("    {} = sub.i64 {}, {}\n", digit_val, curr_byte, ascii_zero));
-
-                // Check if digit_val < 0 (digit_val + 100 < 100, since we work with unsigned comparison)
-                // Actually, let's check if digit_val >= 0 && digit_val <= 9
-                // is_digit = digit_val in [0,9]
-                // We can check: is_zero = (digit_val == 0), ..., is_nine = (digit_val == 9)
-                // OR: check digit_val < 10 and digit_val >= 0
-
-                // Simple approach: check each value 0-9
-                let zero = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 0, 0\n", zero));
-                let is_zero = self.new_var();
-                self.output.push_str(&format!("    {} = eq.i64 {}, {}\n", is_zero, digit_val, zero));
-
-                let mut is_digit_acc = is_zero.clone();
-                for i in 1..=9 {
-                    let val = self.new_var();
-                    self.output.push_str(&format!("    {} = add.i64 {}, 0\n", val, i));
-                    let is_val = self.new_var();
-                    self.output.push_str(&format!("    {} = eq.i64 {}, {}\n", is_val, digit_val, val));
-                    let new_acc = self.new_var();
-                    self.output.push_str(&format!("    {} = add.i64 {}, {}\n", new_acc, is_digit_acc, is_val));
-                    is_digit_acc = new_acc;
-                }
-
-                let is_digit = is_digit_acc;
-
-                self.output.push_str(&format!("    br {}, {}_proc, {}\n", is_digit, read_loop, read_done));
-
-                // Process digit (use digit_val already computed)
-                self.output.push_str(&format!("\n  {}_proc:\n", read_loop));
-                let old_acc = self.new_var();
-                self.output.push_str(&format!("    {} = load.i64 {}\n", old_acc, acc_ptr));
-                let ten = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 10, 0\n", ten));
-                let acc_times_10 = self.new_var();
-                self.output.push_str(&format!("    {} = mul.i64 {}, {}\n", acc_times_10, old_acc, ten));
-
-                let new_acc = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 {}, {}\n", new_acc, acc_times_10, digit_val));
-                self.output.push_str(&format!("    store.i64 {}, {}\n", acc_ptr, new_acc));
-
-                // Read next byte
-                let next_byte = self.new_var();
-                self.output.push_str(&format!("    {} = readbyte\n", next_byte));
-                self.output.push_str(&format!("    store.i64 {}, {}\n", byte_ptr, next_byte));
-                self.output.push_str(&format!("    jmp {}\n", read_loop));
-
-                // Done
-                self.output.push_str(&format!("\n  {}:\n", read_done));
-                let final_val = self.new_var();
-                self.output.push_str(&format!("    {} = load.i64 {}\n", final_val, acc_ptr));
-
-                if let Some(ptr) = self.var_ptrs.get(var_index).cloned() {
-                    self.output.push_str(&format!("    store.i64 {}, {}\n", ptr, final_val));
-                } else {
-                    return Err(format!("Variable index {} out of range", var_index));
+                let value = self.generate_read_int();
+                self.annotate(&value, &Expr::InputNum);
+                self.store_var(*var_index, value);
+                Ok(true)
+            },
+            Statement::InputMany { var_index, count } => {
+                // The integer reader, once per variable
+                for var_index in *var_index..var_index + count {
+                    self.generate_statement(&Statement::Input { var_index })?;
                 }
                 Ok(true)
             },
+            Statement::InputChar { var_index } => {
+                // readbyte already yields the raw byte, or -1 at end of input
+                let byte = self.new_var();
+                self.output.push_str(&format!("    {} = readbyte\n", byte));
+                self.store_var(*var_index, byte);
+                Ok(true)
+            },
             Statement::PrintNum(expr) => {
-                let expr_var = self.generate_expr(expr)?;
-                self.output.push_str(&format!("    print {}\n", expr_var));
+                let expr_var = self.generate_folded_expr(expr)?;
+                if self.portable_print {
+                    self.generate_signed_print(&expr_var, 10);
+                } else {
+                    self.output.push_str(&format!("    print {}\n", expr_var));
+                }
+                Ok(true) // Needs fall-through jump
+            },
+            Statement::PrintNumRadix { expr, radix } => {
+                // Lamina's print is decimal only, so other bases always use the digit loop
+                let expr_var = self.generate_folded_expr(expr)?;
+                self.generate_signed_print(&expr_var, *radix as i64);
                 Ok(true) // Needs fall-through jump
             },
             Statement::PrintChar(expr) => {
-                let expr_var = self.generate_expr(expr)?;
+                let expr_var = self.generate_folded_expr(expr)?;
                 // Print character using writebyte instruction
                 let result = self.new_var();
                 self.output
@@ -296,84 +828,276 @@ impl CodeGenerator {
             },
             Statement::PrintNewline => {
                 // Print newline character (ASCII 10)
-                let newline = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 10, 0\n", newline));
+                let newline = self.const_var(10);
                 let result = self.new_var();
                 self.output
                     .push_str(&format!("    {} = writebyte {}\n", result, newline));
                 Ok(true) // Needs fall-through jump
             },
-            Statement::Conditional { condition, body } => {
-                let cond_var = self.generate_expr(condition)?;
-
-                let then_block = format!("then_{}", self.block_counter);
-                let else_block = format!("else_{}", self.block_counter);
-                self.block_counter += 1;
-
-                // Check if condition is zero (execute when zero)
-                let is_zero = self.new_var();
-                self.output
-                    .push_str(&format!("    {} = eq.i64 {}, 0\n", is_zero, cond_var));
-
-                // Branch: if zero go to then_block, else go to else_block
-                self.output
-                    .push_str(&format!("    br {}, {}, {}\n", is_zero, then_block, else_block));
-
-                // Then block (when condition IS zero)
-                self.output.push_str(&format!("\n  {}:\n", then_block));
-                let mut last_needs_jump = true;
-                for s in body {
-                    last_needs_jump = self.generate_statement(s)?;
-                    // Statements in conditional body are in the same block, no fall-through needed
-                }
-                // Only add jump to else if the last statement needs it (not a goto/return)
-                if last_needs_jump {
-                    self.output.push_str(&format!("    jmp {}\n", else_block));
+            Statement::PrintStr(text) => {
+                for byte in text.bytes() {
+                    let value = self.const_var(byte as i64);
+                    let result = self.new_var();
+                    self.output.push_str(&format!("    {} = writebyte {}\n", result, value));
                 }
-
-                // Else block (continue)
-                self.output.push_str(&format!("\n  {}:\n", else_block));
                 Ok(true) // Needs fall-through jump
             },
-            Statement::Goto(line) => {
-                if *line > 0 {
-                    self.output.push_str(&format!("    jmp line_{}\n", line));
-                    Ok(false) // Already has terminator, no fall-through needed
-                } else {
-                    Err(format!("Invalid goto line: {}", line))
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                self.generate_conditional(condition, body, else_body, None)?;
+                Ok(true) // Needs fall-through jump
+            },
+            Statement::While { .. } => unreachable!("loops are lowered before codegen"),
+            Statement::Goto { target, .. } => {
+                // check_goto_targets already rejected targets outside 1..=max_line
+                let target = self.jump_threads.get(target).unwrap_or(target);
+                self.output.push_str(&format!("    jmp line_{}\n", target));
+                Ok(false) // Already has terminator, no fall-through needed
+            },
+            Statement::GotoDynamic(expr) => {
+                // Lamina has no indirect branch, so compare the target against every
+                // line label in turn. This costs one compare-and-branch per source line
+                // (O(max_line) at runtime); unknown targets end the program.
+                let target = self.generate_folded_expr(expr)?;
+
+                for line in 1..=self.max_line {
+                    let is_line = self.new_var();
+                    self.output
+                        .push_str(&format!("    {} = eq.i64 {}, {}\n", is_line, target, line));
+                    let next = if line < self.max_line {
+                        self.fresh_label("goto_dyn")
+                    } else {
+                        self.fresh_label("goto_dyn_default")
+                    };
+                    self.output
+                        .push_str(&format!("    br {}, line_{}, {}\n", is_line, line, next));
+                    self.emit_label(&next);
                 }
+                self.output.push_str("    ret.i64 0\n");
+                Ok(false) // Already has terminator, no fall-through needed
             },
             Statement::Return(expr) => {
-                let expr_var = self.generate_expr(expr)?;
+                let expr_var = self.generate_folded_expr(expr)?;
                 self.output.push_str(&format!("    ret.i64 {}\n", expr_var));
                 Ok(false) // Already has terminator, no fall-through needed
             },
+            Statement::Eval(expr) => {
+                // The instructions stay, the result temp is simply never used
+                self.generate_folded_expr(expr)?;
+                Ok(true) // Needs fall-through jump
+            },
         }
     }
 
-    fn generate_expr(&mut self, expr: &Expr) -> Result<String, String> {
+    // Read an integer from stdin: skip spaces and newlines, then accumulate
+    // decimal digits until the first non-digit byte. Lamina has no readint, so
+    // the loop is generated inline; returns the temp holding the result.
+    fn generate_read_int(&mut self) -> String {
+        let skip_ws = self.fresh_label("input_skip_ws");
+        let read_start = self.fresh_label("input_start");
+        let read_loop = self.fresh_label("input_loop");
+        let read_digit = self.fresh_label("input_digit");
+        let read_done = self.fresh_label("input_done");
+
+        // Reset the accumulator; the byte cell is written before it is read
+        let acc_ptr = "%input_acc_ptr";
+        let byte_ptr = "%input_byte_ptr";
+        self.output.push_str(&format!("    store.i64 {}, 0\n", acc_ptr));
+
+        self.output.push_str(&format!("    jmp {}\n", skip_ws));
+
+        // Skip whitespace
+        self.emit_label(&skip_ws);
+        let ws_byte = self.new_var();
+        self.output.push_str(&format!("    {} = readbyte\n", ws_byte));
+        self.output.push_str(&format!("    store.i64 {}, {}\n", byte_ptr, ws_byte));
+
+        let space_val = self.const_var(32);
+        let is_space = self.new_var();
+        self.output.push_str(&format!("    {} = eq.i64 {}, {}\n", is_space, ws_byte, space_val));
+
+        let newline_val = self.const_var(10);
+        let is_newline = self.new_var();
+        self.output.push_str(&format!("    {} = eq.i64 {}, {}\n", is_newline, ws_byte, newline_val));
+
+        // Lamina has no `or`: widen both flags, add them, and compare the sum
+        // against 0 so the branch sees a genuine bool
+        let space_flag = self.new_var();
+        self.output.push_str(&format!("    {} = zext.bool.i64 {}\n", space_flag, is_space));
+        let newline_flag = self.new_var();
+        self.output
+            .push_str(&format!("    {} = zext.bool.i64 {}\n", newline_flag, is_newline));
+        let ws_count = self.new_var();
+        self.output
+            .push_str(&format!("    {} = add.i64 {}, {}\n", ws_count, space_flag, newline_flag));
+        let is_ws = self.new_var();
+        self.output.push_str(&format!("    {} = gt.i64 {}, 0\n", is_ws, ws_count));
+
+        self.output.push_str(&format!("    br {}, {}, {}\n", is_ws, skip_ws, read_start));
+
+        // Start reading number
+        self.emit_label(&read_start);
+        self.output.push_str(&format!("    jmp {}\n", read_loop));
+
+        // Read loop
+        self.emit_label(&read_loop);
+        let curr_byte = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", curr_byte, byte_ptr));
+
+        // Check if digit (48-57)
+        // digit = curr_byte - 48, is_digit = (digit >= 0 && digit <= 9)
+        let ascii_zero = self.const_var(48);
+        let digit_val = self.new_var();
+        self.output.push_str(&format!("    {} = sub.i64 {}, {}\n", digit_val, curr_byte, ascii_zero));
+
+        // Range check with two signed comparisons; zero-extended flags are 0/1,
+        // so multiplying them is a boolean AND
+        let is_ge = self.new_var();
+        self.output.push_str(&format!("    {} = ge.i64 {}, 0\n", is_ge, digit_val));
+        let is_le = self.new_var();
+        self.output.push_str(&format!("    {} = le.i64 {}, 9\n", is_le, digit_val));
+        let ge_flag = self.new_var();
+        self.output.push_str(&format!("    {} = zext.bool.i64 {}\n", ge_flag, is_ge));
+        let le_flag = self.new_var();
+        self.output.push_str(&format!("    {} = zext.bool.i64 {}\n", le_flag, is_le));
+        let is_digit = self.new_var();
+        self.output.push_str(&format!("    {} = mul.i64 {}, {}\n", is_digit, ge_flag, le_flag));
+
+        self.output.push_str(&format!("    br {}, {}, {}\n", is_digit, read_digit, read_done));
+
+        // Process digit (use digit_val already computed)
+        self.emit_label(&read_digit);
+        let old_acc = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", old_acc, acc_ptr));
+        let ten = self.const_var(10);
+        let acc_times_10 = self.new_var();
+        self.output.push_str(&format!("    {} = mul.i64 {}, {}\n", acc_times_10, old_acc, ten));
+
+        let new_acc = self.new_var();
+        self.output.push_str(&format!("    {} = add.i64 {}, {}\n", new_acc, acc_times_10, digit_val));
+        self.output.push_str(&format!("    store.i64 {}, {}\n", acc_ptr, new_acc));
+
+        // Read next byte
+        let next_byte = self.new_var();
+        self.output.push_str(&format!("    {} = readbyte\n", next_byte));
+        self.output.push_str(&format!("    store.i64 {}, {}\n", byte_ptr, next_byte));
+        self.output.push_str(&format!("    jmp {}\n", read_loop));
+
+        // Done
+        self.emit_label(&read_done);
+        let final_val = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", final_val, acc_ptr));
+        final_val
+    }
+
+    fn generate_folded_expr(&mut self, expr: &Expr) -> Result<String, CompileError> {
+        let folded = self.fold(expr);
+        let value = self.generate_expr(&folded)?;
+        self.annotate(&value, expr);
+        Ok(value)
+    }
+
+    // With source comments on, end the instruction that just defined `temp`
+    // with the expression it holds. Values that aren't the last instruction's
+    // result (constants, variables, results followed by overflow checks) are
+    // left alone.
+    fn annotate(&mut self, temp: &str, expr: &Expr) {
+        if self.source_lines.is_none() {
+            return;
+        }
+        let Some(body) = self.output.strip_suffix('\n') else {
+            return;
+        };
+        let last_line = &body[body.rfind('\n').map_or(0, |idx| idx + 1)..];
+        if last_line.trim_start().starts_with(&format!("{} = ", temp)) {
+            self.output.truncate(body.len());
+            self.output.push_str(&format!(" # = {}\n", expr));
+        }
+    }
+
+    // `expr` with constant subtrees folded, or unchanged at -O0
+    fn fold(&self, expr: &Expr) -> Expr {
+        if self.opt_level >= OptLevel::O1 { Self::fold_constants(expr, self.checked) } else { expr.clone() }
+    }
+
+    // Collapse fully-constant subtrees into a single Expr::Number.
+    // Arithmetic wraps like the generated i64 code; modulo by zero is left unfolded
+    // (validate rejects it), and so is overflow in checked mode, where it has to reach the trap.
+    pub(crate) fn fold_constants(expr: &Expr, checked: bool) -> Expr {
+        let fold = |expr: &Expr| Self::fold_constants(expr, checked);
         match expr {
-            Expr::Number(n) => {
-                let var = self.new_var();
-                self.output.push_str(&format!("    {} = add.i64 {}, 0\n", var, n));
-                Ok(var)
+            Expr::Number(_) | Expr::Var(_) | Expr::InputNum => expr.clone(),
+            Expr::Add(left, right) => match (fold(left), fold(right)) {
+                (Expr::Number(l), Expr::Number(r)) if !checked || l.checked_add(r).is_some() => {
+                    Expr::Number(l.wrapping_add(r))
+                },
+                (l, r) => Expr::Add(Box::new(l), Box::new(r)),
+            },
+            Expr::Sub(left, right) => match (fold(left), fold(right)) {
+                (Expr::Number(l), Expr::Number(r)) if !checked || l.checked_sub(r).is_some() => {
+                    Expr::Number(l.wrapping_sub(r))
+                },
+                (l, r) => Expr::Sub(Box::new(l), Box::new(r)),
+            },
+            Expr::Mul(left, right) => match (fold(left), fold(right)) {
+                (Expr::Number(l), Expr::Number(r)) if !checked || l.checked_mul(r).is_some() => {
+                    Expr::Number(l.wrapping_mul(r))
+                },
+                (l, r) => Expr::Mul(Box::new(l), Box::new(r)),
+            },
+            Expr::Mod(left, right) => match (fold(left), fold(right)) {
+                (Expr::Number(l), Expr::Number(r)) if r != 0 => Expr::Number(l.wrapping_rem(r)),
+                (l, r) => Expr::Mod(Box::new(l), Box::new(r)),
             },
+            Expr::Pow(base, exp) => match fold(base) {
+                Expr::Number(b) if !checked || b.checked_pow(*exp).is_some() => Expr::Number(b.wrapping_pow(*exp)),
+                b => Expr::Pow(Box::new(b), *exp),
+            },
+            Expr::Neg(operand) => match fold(operand) {
+                Expr::Number(n) if !checked || n.checked_neg().is_some() => Expr::Number(n.wrapping_neg()),
+                operand => Expr::Neg(Box::new(operand)),
+            },
+            Expr::Eq(left, right) => match (fold(left), fold(right)) {
+                (Expr::Number(l), Expr::Number(r)) => Expr::Number((l == r) as i64),
+                (l, r) => Expr::Eq(Box::new(l), Box::new(r)),
+            },
+            Expr::Lt(left, right) => match (fold(left), fold(right)) {
+                (Expr::Number(l), Expr::Number(r)) => Expr::Number((l < r) as i64),
+                (l, r) => Expr::Lt(Box::new(l), Box::new(r)),
+            },
+            Expr::Gt(left, right) => match (fold(left), fold(right)) {
+                (Expr::Number(l), Expr::Number(r)) => Expr::Number((l > r) as i64),
+                (l, r) => Expr::Gt(Box::new(l), Box::new(r)),
+            },
+        }
+    }
+
+    fn generate_expr(&mut self, expr: &Expr) -> Result<String, CompileError> {
+        match expr {
+            Expr::Number(n) => Ok(self.const_var(*n)),
             Expr::Var(index) => {
-                // Load from memory
-                if let Some(ptr) = self.var_ptrs.get(index).cloned() {
-                    let loaded = self.new_var();
-                    self.output.push_str(&format!("    {} = load.i64 {}\n", loaded, ptr));
-                    Ok(loaded)
-                } else {
-                    Err(format!("Variable index {} out of range", index))
+                if let Some(value) = self.var_regs.get(index) {
+                    return Ok(value.clone());
                 }
+                // Load from memory
+                let ptr = self.var_ptr(*index);
+                let loaded = self.new_var();
+                self.output.push_str(&format!("    {} = load.i64 {}\n", loaded, ptr));
+                Ok(loaded)
             },
+            Expr::InputNum => Ok(self.generate_read_int()),
             Expr::Add(left, right) => {
                 let left_var = self.generate_expr(left)?;
                 let right_var = self.generate_expr(right)?;
                 let result = self.new_var();
                 self.output
                     .push_str(&format!("    {} = add.i64 {}, {}\n", result, left_var, right_var));
+                if self.checked {
+                    self.generate_overflow_check(expr, &left_var, &right_var, &result);
+                }
                 Ok(result)
             },
             Expr::Sub(left, right) => {
@@ -382,6 +1106,9 @@ impl CodeGenerator {
                 let result = self.new_var();
                 self.output
                     .push_str(&format!("    {} = sub.i64 {}, {}\n", result, left_var, right_var));
+                if self.checked {
+                    self.generate_overflow_check(expr, &left_var, &right_var, &result);
+                }
                 Ok(result)
             },
             Expr::Mul(left, right) => {
@@ -390,11 +1117,517 @@ impl CodeGenerator {
                 let result = self.new_var();
                 self.output
                     .push_str(&format!("    {} = mul.i64 {}, {}\n", result, left_var, right_var));
+                if self.checked {
+                    self.generate_overflow_check(expr, &left_var, &right_var, &result);
+                }
+                Ok(result)
+            },
+            Expr::Mod(left, right) => {
+                // Lamina has no remainder op: a % b = a - (a / b) * b
+                let left_var = self.generate_expr(left)?;
+                let right_var = self.generate_expr(right)?;
+                if self.checked {
+                    self.generate_rem_overflow_check(&left_var, &right_var);
+                }
+                let quotient = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = div.i64 {}, {}\n", quotient, left_var, right_var));
+                let product = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = mul.i64 {}, {}\n", product, quotient, right_var));
+                let result = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = sub.i64 {}, {}\n", result, left_var, product));
+                Ok(result)
+            },
+            Expr::Neg(operand) => {
+                let operand_var = self.generate_expr(operand)?;
+                let result = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = sub.i64 0, {}\n", result, operand_var));
+                if self.checked {
+                    self.generate_overflow_check(expr, "0", &operand_var, &result);
+                }
+                Ok(result)
+            },
+            Expr::Pow(base, exp) => {
+                if *exp == 0 {
+                    // x^0 is 1 without evaluating x, unless that reads input
+                    if base.reads_input() {
+                        self.generate_expr(base)?;
+                    }
+                    return Ok(self.const_var(1));
+                }
+                // Evaluate the base once, then multiply it in exp - 1 more times
+                let base_var = self.generate_expr(base)?;
+                let mut result = base_var.clone();
+                for _ in 1..*exp {
+                    let product = self.new_var();
+                    self.output
+                        .push_str(&format!("    {} = mul.i64 {}, {}\n", product, result, base_var));
+                    if self.checked {
+                        self.generate_overflow_check(expr, &result, &base_var, &product);
+                    }
+                    result = product;
+                }
                 Ok(result)
             },
+            Expr::Eq(left, right) => self.generate_comparison(left, right, "eq"),
+            Expr::Lt(left, right) => self.generate_comparison(left, right, "lt"),
+            Expr::Gt(left, right) => self.generate_comparison(left, right, "gt"),
         }
     }
 
+    // Comparisons produce a bool, widened to the i64 every expression evaluates to
+    fn generate_comparison(&mut self, left: &Expr, right: &Expr, op: &str) -> Result<String, CompileError> {
+        let left_var = self.generate_expr(left)?;
+        let right_var = self.generate_expr(right)?;
+        let flag = self.new_var();
+        self.output
+            .push_str(&format!("    {} = {}.i64 {}, {}\n", flag, op, left_var, right_var));
+        let result = self.new_var();
+        self.output
+            .push_str(&format!("    {} = zext.bool.i64 {}\n", result, flag));
+        Ok(result)
+    }
+
+    // Branch to the overflow trap if `result`, the wrapped value of `left op right`, overflowed.
+    fn generate_overflow_check(&mut self, expr: &Expr, left: &str, right: &str, result: &str) {
+        let ok = self.fresh_label("no_overflow");
+        self.needs_overflow_trap = true;
+
+        match expr {
+            Expr::Add(..) | Expr::Sub(..) | Expr::Neg(_) => {
+                // a + b overflowed iff (b < 0) != (r < a); a - b overflowed iff (b > 0) != (r < a).
+                // Negation is 0 - b.
+                let sign_op = if matches!(expr, Expr::Add(..)) { "lt" } else { "gt" };
+                let rhs_sign = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = {}.i64 {}, 0\n", rhs_sign, sign_op, right));
+                let rhs_flag = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = zext.bool.i64 {}\n", rhs_flag, rhs_sign));
+                let below = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = lt.i64 {}, {}\n", below, result, left));
+                let below_flag = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = zext.bool.i64 {}\n", below_flag, below));
+                let overflow = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = ne.i64 {}, {}\n", overflow, rhs_flag, below_flag));
+                self.output
+                    .push_str(&format!("    br {}, overflow_trap, {}\n", overflow, ok));
+            },
+            _ => {
+                // a * b overflowed iff r / a != b. a == 0 never overflows, and a == -1 is
+                // checked on its own since i64::MIN / -1 would fault in the division.
+                let nonzero = self.fresh_label("mul_nonzero");
+                let neg_one = self.fresh_label("mul_neg_one");
+                let divide = self.fresh_label("mul_divide");
+
+                let is_zero = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = eq.i64 {}, 0\n", is_zero, left));
+                self.output
+                    .push_str(&format!("    br {}, {}, {}\n", is_zero, ok, nonzero));
+
+                self.emit_label(&nonzero);
+                let is_neg_one = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = eq.i64 {}, -1\n", is_neg_one, left));
+                self.output
+                    .push_str(&format!("    br {}, {}, {}\n", is_neg_one, neg_one, divide));
+
+                // -b only overflows for i64::MIN, the one value where b and -b are both negative
+                self.emit_label(&neg_one);
+                let rhs_neg = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = lt.i64 {}, 0\n", rhs_neg, right));
+                let rhs_flag = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = zext.bool.i64 {}\n", rhs_flag, rhs_neg));
+                let result_neg = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = lt.i64 {}, 0\n", result_neg, result));
+                let result_flag = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = zext.bool.i64 {}\n", result_flag, result_neg));
+                let both_neg = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = mul.i64 {}, {}\n", both_neg, rhs_flag, result_flag));
+                self.output
+                    .push_str(&format!("    br {}, overflow_trap, {}\n", both_neg, ok));
+
+                self.emit_label(&divide);
+                let quotient = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = div.i64 {}, {}\n", quotient, result, left));
+                let mismatch = self.new_var();
+                self.output
+                    .push_str(&format!("    {} = ne.i64 {}, {}\n", mismatch, quotient, right));
+                self.output
+                    .push_str(&format!("    br {}, overflow_trap, {}\n", mismatch, ok));
+            },
+        }
+
+        self.emit_label(&ok);
+    }
+
+    // Branch to the overflow trap before `left % right` divides i64::MIN by -1,
+    // which would fault rather than wrap. i64::MIN is the one negative value
+    // whose negation is negative too.
+    fn generate_rem_overflow_check(&mut self, left: &str, right: &str) {
+        let ok = self.fresh_label("no_overflow");
+        self.needs_overflow_trap = true;
+
+        let mut flags = Vec::new();
+        let negated = self.new_var();
+        self.output
+            .push_str(&format!("    {} = sub.i64 0, {}\n", negated, left));
+        for (op, operand, constant) in [("eq", right, "-1"), ("lt", left, "0"), ("lt", &negated, "0")] {
+            let test = self.new_var();
+            self.output
+                .push_str(&format!("    {} = {}.i64 {}, {}\n", test, op, operand, constant));
+            let flag = self.new_var();
+            self.output
+                .push_str(&format!("    {} = zext.bool.i64 {}\n", flag, test));
+            flags.push(flag);
+        }
+        let both = self.new_var();
+        self.output
+            .push_str(&format!("    {} = mul.i64 {}, {}\n", both, flags[0], flags[1]));
+        let overflow = self.new_var();
+        self.output
+            .push_str(&format!("    {} = mul.i64 {}, {}\n", overflow, both, flags[2]));
+        self.output
+            .push_str(&format!("    br {}, overflow_trap, {}\n", overflow, ok));
+
+        self.emit_label(&ok);
+    }
+
+    // The statements of a conditional's body or else part, all in one block.
+    // Whatever follows a goto or return can't run, and would land after the
+    // block's terminator, so it is dropped. Returns whether the block still
+    // needs a jump out, i.e. it didn't end in a terminator.
+    fn generate_body(&mut self, body: &[Statement]) -> Result<bool, CompileError> {
+        for s in body {
+            if !self.generate_statement(s)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // Lower a conditional, leaving the continuation block open.
+    //
+    // An else part that is just another conditional (an else-if) doesn't get its own
+    // continuation: `join` passes the outermost one down, so a chain becomes a linear
+    // run of compare-and-branch blocks that all rejoin at a single label.
+    fn generate_conditional(
+        &mut self,
+        condition: &Expr,
+        body: &[Statement],
+        else_body: &[Statement],
+        join: Option<&str>,
+    ) -> Result<(), CompileError> {
+        let cond_var = self.generate_folded_expr(condition)?;
+
+        let then_block = self.fresh_label("then");
+        let else_block = match (join, else_body.is_empty()) {
+            (Some(join), true) => join.to_string(),
+            _ => self.fresh_label("else"),
+        };
+        let end_block = match join {
+            Some(join) => join.to_string(),
+            None => self.fresh_label("endif"),
+        };
+
+        // Check if condition is zero (execute when zero), or nonzero when truthy
+        let test = if self.truthy_conditionals { "ne" } else { "eq" };
+        let takes_body = self.new_var();
+        self.output
+            .push_str(&format!("    {} = {}.i64 {}, 0\n", takes_body, test, cond_var));
+
+        // Branch: if the test holds go to then_block, else go to else_block
+        self.output
+            .push_str(&format!("    br {}, {}, {}\n", takes_body, then_block, else_block));
+
+        // Then block (when the test holds)
+        self.emit_label(&then_block);
+        let last_needs_jump = self.generate_body(body)?;
+        if else_body.is_empty() {
+            // Only add jump to else if the last statement needs it (not a goto/return)
+            if last_needs_jump {
+                self.output.push_str(&format!("    jmp {}\n", else_block));
+            }
+
+            // Else block (continue), unless it's the enclosing chain's to open
+            if join.is_none() {
+                self.emit_label(&else_block);
+            }
+            return Ok(());
+        }
+
+        // Then and else both rejoin at the end block
+        if last_needs_jump {
+            self.output.push_str(&format!("    jmp {}\n", end_block));
+        }
+
+        // Else block (when condition is NOT zero)
+        self.emit_label(&else_block);
+        if let [Statement::Conditional {
+            condition,
+            body,
+            else_body,
+        }] = else_body
+        {
+            self.generate_conditional(condition, body, else_body, Some(&end_block))?;
+        } else {
+            let last_needs_jump = self.generate_body(else_body)?;
+            if last_needs_jump {
+                self.output.push_str(&format!("    jmp {}\n", end_block));
+            }
+        }
+
+        if join.is_none() {
+            self.emit_label(&end_block);
+        }
+        Ok(())
+    }
+
+    // Write `value` as a signed number in base `radix` (2..=16, lowercase digits)
+    // plus a newline, one writebyte per character.
+    //
+    // The digits are taken from the value negated into the non-positive range,
+    // which (unlike the positive range) can hold i64::MIN. The leading digits come
+    // from that magnitude divided by the radix, so the place values (a power of the
+    // radix scaled up to the leading digit, then divided back down one digit at a
+    // time) always fit; the last digit is written on its own.
+    fn generate_signed_print(&mut self, value: &str, radix: i64) {
+        let sign = self.fresh_label("print_sign");
+        let flip = self.fresh_label("print_flip");
+        let split = self.fresh_label("print_split");
+        let scale = self.fresh_label("print_scale");
+        let scale_up = self.fresh_label("print_scale_up");
+        let digits = self.fresh_label("print_digit");
+        let last = self.fresh_label("print_last");
+
+        let mag_ptr = "%print_mag_ptr";
+        let rest_ptr = "%print_rest_ptr";
+        let pow_ptr = "%print_pow_ptr";
+        self.output.push_str(&format!("    store.i64 {}, {}\n", mag_ptr, value));
+        self.output.push_str(&format!("    store.i64 {}, 1\n", pow_ptr));
+        let is_neg = self.new_var();
+        self.output.push_str(&format!("    {} = lt.i64 {}, 0\n", is_neg, value));
+        self.output.push_str(&format!("    br {}, {}, {}\n", is_neg, sign, flip));
+
+        // Negative: write '-' and keep the value as is
+        self.emit_label(&sign);
+        let minus = self.const_var(b'-' as i64);
+        let result = self.new_var();
+        self.output.push_str(&format!("    {} = writebyte {}\n", result, minus));
+        self.output.push_str(&format!("    jmp {}\n", split));
+
+        // Non-negative: negate, which can't overflow
+        self.emit_label(&flip);
+        let negated = self.new_var();
+        self.output.push_str(&format!("    {} = sub.i64 0, {}\n", negated, value));
+        self.output.push_str(&format!("    store.i64 {}, {}\n", mag_ptr, negated));
+        self.output.push_str(&format!("    jmp {}\n", split));
+
+        // Single-digit magnitudes have no leading digits
+        self.emit_label(&split);
+        let mag = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", mag, mag_ptr));
+        let rest = self.new_var();
+        self.output.push_str(&format!("    {} = div.i64 {}, {}\n", rest, mag, radix));
+        self.output.push_str(&format!("    store.i64 {}, {}\n", rest_ptr, rest));
+        let has_rest = self.new_var();
+        self.output.push_str(&format!("    {} = lt.i64 {}, 0\n", has_rest, rest));
+        self.output.push_str(&format!("    br {}, {}, {}\n", has_rest, scale, last));
+
+        // Grow the place value while at least two leading digits remain above it
+        self.emit_label(&scale);
+        let rest = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", rest, rest_ptr));
+        let pow = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", pow, pow_ptr));
+        let quotient = self.new_var();
+        self.output.push_str(&format!("    {} = div.i64 {}, {}\n", quotient, rest, pow));
+        let more = self.new_var();
+        self.output.push_str(&format!("    {} = le.i64 {}, {}\n", more, quotient, -radix));
+        self.output.push_str(&format!("    br {}, {}, {}\n", more, scale_up, digits));
+
+        self.emit_label(&scale_up);
+        let pow = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", pow, pow_ptr));
+        let next_pow = self.new_var();
+        self.output.push_str(&format!("    {} = mul.i64 {}, {}\n", next_pow, pow, radix));
+        self.output.push_str(&format!("    store.i64 {}, {}\n", pow_ptr, next_pow));
+        self.output.push_str(&format!("    jmp {}\n", scale));
+
+        self.emit_label(&digits);
+        let rest = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", rest, rest_ptr));
+        let pow = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", pow, pow_ptr));
+        let quotient = self.new_var();
+        self.output.push_str(&format!("    {} = div.i64 {}, {}\n", quotient, rest, pow));
+        self.generate_digit_write(&quotient, radix);
+        let next_pow = self.new_var();
+        self.output.push_str(&format!("    {} = div.i64 {}, {}\n", next_pow, pow, radix));
+        self.output.push_str(&format!("    store.i64 {}, {}\n", pow_ptr, next_pow));
+        let remaining = self.new_var();
+        self.output.push_str(&format!("    {} = gt.i64 {}, 0\n", remaining, next_pow));
+        self.output.push_str(&format!("    br {}, {}, {}\n", remaining, digits, last));
+
+        self.emit_label(&last);
+        let mag = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 {}\n", mag, mag_ptr));
+        self.generate_digit_write(&mag, radix);
+        let newline = self.const_var(10);
+        let result = self.new_var();
+        self.output.push_str(&format!("    {} = writebyte {}\n", result, newline));
+    }
+
+    // Write the lowest digit of the non-positive `quotient`: digit = -(quotient % radix),
+    // written as '0' + digit, or 'a' + digit - 10 past 9 by adding the gap between '9' and 'a'
+    fn generate_digit_write(&mut self, quotient: &str, radix: i64) {
+        let tens = self.new_var();
+        self.output.push_str(&format!("    {} = div.i64 {}, {}\n", tens, quotient, radix));
+        let tens_scaled = self.new_var();
+        self.output.push_str(&format!("    {} = mul.i64 {}, {}\n", tens_scaled, tens, radix));
+        let neg_digit = self.new_var();
+        self.output.push_str(&format!("    {} = sub.i64 {}, {}\n", neg_digit, quotient, tens_scaled));
+        let ascii_zero = self.const_var(b'0' as i64);
+        let byte = self.new_var();
+        self.output.push_str(&format!("    {} = sub.i64 {}, {}\n", byte, ascii_zero, neg_digit));
+        let byte = if radix > 10 {
+            let is_letter = self.new_var();
+            self.output.push_str(&format!("    {} = lt.i64 {}, -9\n", is_letter, neg_digit));
+            let letter_flag = self.new_var();
+            self.output
+                .push_str(&format!("    {} = zext.bool.i64 {}\n", letter_flag, is_letter));
+            let letter_gap = self.new_var();
+            self.output.push_str(&format!(
+                "    {} = mul.i64 {}, {}\n",
+                letter_gap,
+                letter_flag,
+                b'a' - b'9' - 1
+            ));
+            let shifted = self.new_var();
+            self.output.push_str(&format!("    {} = add.i64 {}, {}\n", shifted, byte, letter_gap));
+            shifted
+        } else {
+            byte
+        };
+        let result = self.new_var();
+        self.output.push_str(&format!("    {} = writebyte {}\n", result, byte));
+    }
+
+    // Count one more executed statement, branching to the line limit trap past `max_lines`
+    fn generate_step_count(&mut self, max_lines: u64) {
+        let steps = self.new_var();
+        self.output.push_str(&format!("    {} = load.i64 %steps_ptr\n", steps));
+        let next_steps = self.new_var();
+        self.output.push_str(&format!("    {} = add.i64 {}, 1\n", next_steps, steps));
+        self.output.push_str(&format!("    store.i64 %steps_ptr, {}\n", next_steps));
+        let limit = self.operand(max_lines.min(i64::MAX as u64) as i64);
+        let exceeded = self.new_var();
+        self.output
+            .push_str(&format!("    {} = gt.i64 {}, {}\n", exceeded, next_steps, limit));
+        let ok = self.fresh_label("steps_ok");
+        self.output
+            .push_str(&format!("    br {}, line_limit_trap, {}\n", exceeded, ok));
+        self.emit_label(&ok);
+    }
+
+    // Shared target of every overflow (or line limit) check: report and exit with status 1
+    fn generate_trap(&mut self, label: &str, message: &[u8]) {
+        self.emit_label(label);
+        for byte in message {
+            let value = self.const_var(*byte as i64);
+            let result = self.new_var();
+            self.output
+                .push_str(&format!("    {} = writebyte {}\n", result, value));
+        }
+        self.output.push_str("    ret.i64 1\n");
+    }
+
+    // Give a variable a new value: rebind its SSA temp, or store to its slot
+    fn store_var(&mut self, var_index: usize, value: String) {
+        if let Some(current) = self.var_regs.get_mut(&var_index) {
+            *current = value;
+        } else {
+            let ptr = self.var_ptr(var_index);
+            self.output.push_str(&format!("    store.i64 {}, {}\n", ptr, value));
+        }
+    }
+
+    // Every index a statement or expression mentions is found by
+    // collect_used_variables and allocated up front, so the lookup can't miss
+    fn var_ptr(&self, var_index: usize) -> String {
+        self.var_ptrs[&var_index].clone()
+    }
+
+    // Every internal block label comes from here. The counter is shared by all
+    // prefixes, so no two calls return the same name, and no prefix is `line`,
+    // so they can't collide with the `line_N`/`line_N_K` source line labels.
+    fn fresh_label(&mut self, prefix: &str) -> String {
+        debug_assert_ne!(prefix, "line", "line_N labels are reserved for source lines");
+        let label = format!("{}_{}", prefix, self.block_counter);
+        self.block_counter += 1;
+        label
+    }
+
+    fn emit_label(&mut self, label: &str) {
+        self.output.push_str(&format!("\n  {}:\n", label));
+        // Temps from the previous block don't dominate this one
+        self.block_consts.clear();
+    }
+
+    // Materialize a constant, reusing an earlier temp from the same block from -O1 on
+    fn const_var(&mut self, value: i64) -> String {
+        if let (10, Some(var)) = (value, &self.newline_const) {
+            return var.clone();
+        }
+        if let Some(var) = self.block_consts.get(&value) {
+            return var.clone();
+        }
+        let var = if i32::try_from(value).is_ok() {
+            let var = self.new_var();
+            self.output.push_str(&format!("    {} = add.i64 {}, 0\n", var, value));
+            var
+        } else {
+            self.wide_const(value)
+        };
+        if self.opt_level >= OptLevel::O1 {
+            self.block_consts.insert(value, var.clone());
+        }
+        var
+    }
+
+    // Lamina only encodes immediates that fit in 32 bits, so a wider constant is
+    // built from its high half and two 16-bit pieces: (hi * 2^16 + mid) * 2^16 + lo
+    fn wide_const(&mut self, value: i64) -> String {
+        let mut acc = self.new_var();
+        self.output.push_str(&format!("    {} = add.i64 {}, 0\n", acc, value >> 32));
+        for shift in [16, 0] {
+            let scaled = self.new_var();
+            self.output.push_str(&format!("    {} = mul.i64 {}, 65536\n", scaled, acc));
+            acc = self.new_var();
+            self.output
+                .push_str(&format!("    {} = add.i64 {}, {}\n", acc, scaled, (value >> shift) & 0xffff));
+        }
+        acc
+    }
+
+    // `value` as an instruction operand: an immediate when it fits, else a temp
+    fn operand(&mut self, value: i64) -> String {
+        if i32::try_from(value).is_ok() { value.to_string() } else { self.const_var(value) }
+    }
+
     fn new_var(&mut self) -> String {
         let var = format!("%t{}", self.var_counter);
         self.var_counter += 1;
@@ -0,0 +1,136 @@
+// Lines of unchanged context around each change
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Diffs `old` against `new` line by line in unified format, headed by
+/// `--- old_name` and `+++ new_name`, with three lines of context per hunk.
+/// Returns an empty string when the texts have the same lines.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let edits = edit_script(&old, &new);
+    if edits.iter().all(|edit| *edit == Edit::Keep) {
+        return String::new();
+    }
+
+    // Position of each edit in both texts, before it applies
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut o, mut n) = (0, 0);
+    for edit in &edits {
+        positions.push((o, n));
+        match edit {
+            Edit::Keep => {
+                o += 1;
+                n += 1;
+            },
+            Edit::Delete => o += 1,
+            Edit::Insert => n += 1,
+        }
+    }
+
+    // Changes closer than twice the context share a hunk
+    let changes: Vec<usize> = (0..edits.len()).filter(|&idx| edits[idx] != Edit::Keep).collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for idx in changes {
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(hunk) if start <= hunk.1 => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        let edits = &edits[start..end];
+        let old_count = edits.iter().filter(|edit| **edit != Edit::Insert).count();
+        let new_count = edits.iter().filter(|edit| **edit != Edit::Delete).count();
+        let (old_start, new_start) = positions[start];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_count),
+            hunk_range(new_start, new_count)
+        ));
+        for (edit, &(o, n)) in edits.iter().zip(&positions[start..end]) {
+            match edit {
+                Edit::Keep => out.push_str(&format!(" {}\n", old[o])),
+                Edit::Delete => out.push_str(&format!("-{}\n", old[o])),
+                Edit::Insert => out.push_str(&format!("+{}\n", new[n])),
+            }
+        }
+    }
+    out
+}
+
+// `start,count` for a hunk header, 1-based. An empty range names the line
+// before it, and a count of one is left out.
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+// Shortest edit script turning `old` into `new` (Myers' O(ND) algorithm).
+//
+// `trace[d]` holds, for each diagonal k = x - y in -d..=d (step 2), the
+// furthest x reached with d edits; walking it backwards recovers the path.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]);
+            let mut x = if down { v[(offset + k + 1) as usize] } else { v[(offset + k - 1) as usize] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                trace.push((-d..=d).step_by(2).map(|k| v[(offset + k) as usize]).collect());
+                break 'search;
+            }
+        }
+        trace.push((-d..=d).step_by(2).map(|k| v[(offset + k) as usize]).collect());
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let previous = &trace[d as usize - 1];
+        let furthest = |k: isize| previous[((k + d - 1) / 2) as usize];
+        let k = x - y;
+        let down = k == -d || (k != d && furthest(k - 1) < furthest(k + 1));
+        let prev_k = if down { k + 1 } else { k - 1 };
+        let prev_x = furthest(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        // The snake after the edit, then the edit itself
+        let (mid_x, mid_y) = if down { (prev_x, prev_y + 1) } else { (prev_x + 1, prev_y) };
+        while x > mid_x && y > mid_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        edits.push(if down { Edit::Insert } else { Edit::Delete });
+        x = prev_x;
+        y = prev_y;
+    }
+    // What's left is the common prefix
+    edits.extend(std::iter::repeat_n(Edit::Keep, x as usize));
+    edits.reverse();
+    edits
+}
@@ -0,0 +1,141 @@
+use crate::ast::{Expr, Program, Statement};
+
+/// Renders the parse tree as a Graphviz DOT digraph.
+///
+/// Every statement and expression gets its own node, so repeated
+/// subexpressions show up as separate subtrees rather than shared nodes.
+pub fn ast_to_dot(program: &Program) -> String {
+    let mut writer = DotWriter {
+        output: String::new(),
+        next_id: 0,
+    };
+
+    writer.output.push_str("digraph ast {\n");
+    writer.output.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+    let root = writer.node("program");
+    for (stmt, line) in &program.statements {
+        let child = writer.statement(stmt, Some(*line));
+        writer.edge(&root, &child, None);
+    }
+    writer.output.push_str("}\n");
+    writer.output
+}
+
+struct DotWriter {
+    output: String,
+    next_id: usize,
+}
+
+impl DotWriter {
+    fn node(&mut self, label: &str) -> String {
+        let id = format!("n{}", self.next_id);
+        self.next_id += 1;
+        let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+        self.output.push_str(&format!("  {} [label=\"{}\"];\n", id, label));
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: Option<&str>) {
+        match label {
+            Some(label) => self
+                .output
+                .push_str(&format!("  {} -> {} [label=\"{}\"];\n", from, to, label)),
+            None => self.output.push_str(&format!("  {} -> {};\n", from, to)),
+        }
+    }
+
+    // Top-level statements are labeled with their source line; nested ones aren't
+    fn statement(&mut self, stmt: &Statement, line: Option<usize>) -> String {
+        let label = match stmt {
+            Statement::Assign { var_index, .. } => format!("assign var{}", var_index),
+            Statement::Input { var_index } => format!("input var{}", var_index),
+            Statement::InputMany { var_index, count } => {
+                format!("input var{}..var{}", var_index, var_index + count - 1)
+            },
+            Statement::InputChar { var_index } => format!("input_char var{}", var_index),
+            Statement::PrintNum(_) => "print_num".to_string(),
+            Statement::PrintNumRadix { radix, .. } => format!("print_num base {}", radix),
+            Statement::PrintChar(_) => "print_char".to_string(),
+            Statement::PrintNewline => "print_newline".to_string(),
+            Statement::PrintStr(text) => format!("print_str {:?}", text),
+            Statement::Conditional { .. } => "if == 0".to_string(),
+            Statement::While { .. } => "while == 0".to_string(),
+            Statement::Goto { target, .. } => format!("goto {}", target),
+            Statement::GotoDynamic(_) => "goto".to_string(),
+            Statement::Return(_) => "return".to_string(),
+            Statement::Eval(_) => "eval".to_string(),
+        };
+        let label = match line {
+            Some(line) => format!("{}: {}", line, label),
+            None => label,
+        };
+        let id = self.node(&label);
+
+        match stmt {
+            Statement::Assign { value: expr, .. }
+            | Statement::PrintNum(expr)
+            | Statement::PrintNumRadix { expr, .. }
+            | Statement::PrintChar(expr)
+            | Statement::GotoDynamic(expr)
+            | Statement::Return(expr)
+            | Statement::Eval(expr) => {
+                let child = self.expr(expr);
+                self.edge(&id, &child, None);
+            },
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                let child = self.expr(condition);
+                self.edge(&id, &child, Some("cond"));
+                for s in body {
+                    let child = self.statement(s, None);
+                    self.edge(&id, &child, Some("then"));
+                }
+                for s in else_body {
+                    let child = self.statement(s, None);
+                    self.edge(&id, &child, Some("else"));
+                }
+            },
+            Statement::While { condition, body, .. } => {
+                let child = self.expr(condition);
+                self.edge(&id, &child, Some("cond"));
+                for (s, span) in body {
+                    let child = self.statement(s, Some(span.line));
+                    self.edge(&id, &child, Some("body"));
+                }
+            },
+            Statement::Input { .. }
+            | Statement::InputMany { .. }
+            | Statement::InputChar { .. }
+            | Statement::PrintNewline
+            | Statement::PrintStr(_)
+            | Statement::Goto { .. } => {},
+        }
+        id
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        let (label, children): (String, Vec<&Expr>) = match expr {
+            Expr::Number(n) => (n.to_string(), vec![]),
+            Expr::Var(index) => (format!("var{}", index), vec![]),
+            Expr::InputNum => ("input".to_string(), vec![]),
+            Expr::Add(l, r) => ("+".to_string(), vec![l, r]),
+            Expr::Sub(l, r) => ("-".to_string(), vec![l, r]),
+            Expr::Mul(l, r) => ("*".to_string(), vec![l, r]),
+            Expr::Mod(l, r) => ("%".to_string(), vec![l, r]),
+            Expr::Eq(l, r) => ("=".to_string(), vec![l, r]),
+            Expr::Lt(l, r) => ("<".to_string(), vec![l, r]),
+            Expr::Gt(l, r) => (">".to_string(), vec![l, r]),
+            Expr::Pow(base, exp) => (format!("^ {}", exp), vec![base]),
+            Expr::Neg(operand) => ("neg".to_string(), vec![operand]),
+        };
+        let id = self.node(&label);
+        for child in children {
+            let child_id = self.expr(child);
+            self.edge(&id, &child_id, None);
+        }
+        id
+    }
+}
@@ -0,0 +1,63 @@
+use crate::ast::Span;
+use crate::lexer::normalize_line_endings;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompileError {
+    // Lexical error at a source location
+    Lex { line: usize, col: usize, message: String },
+    // Parse error at a token position (line/col of that token)
+    Parse {
+        position: usize,
+        line: usize,
+        col: usize,
+        message: String,
+    },
+    // Code generation error, with the statement's position when known
+    Codegen { span: Option<Span>, message: String },
+    // Reading the source failed
+    Io { message: String },
+}
+
+impl CompileError {
+    /// Source location (line, col) of the error, if known.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            CompileError::Lex { line, col, .. } | CompileError::Parse { line, col, .. } => Some((*line, *col)),
+            CompileError::Codegen { span, .. } => span.map(|span| (span.line, span.col)),
+            CompileError::Io { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Lex { line, col, message } => write!(f, "{} at line {}, col {}", message, line, col),
+            CompileError::Parse { message, .. } => write!(f, "{}", message),
+            CompileError::Codegen {
+                span: Some(span),
+                message,
+            } => write!(f, "{} at line {}, col {}", message, span.line, span.col),
+            CompileError::Codegen { span: None, message } => write!(f, "{}", message),
+            CompileError::Io { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for CompileError {}
+
+/// Renders the source line at `line` with a `^` caret under `col`.
+///
+/// Columns are counted in characters, so a Hangul syllable is one column,
+/// matching how the lexer advances `col`.
+pub fn render_diagnostic(source: &str, line: usize, col: usize) -> String {
+    let source = normalize_line_endings(source);
+    let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+    let gutter = line.to_string();
+    let pad = " ".repeat(gutter.len());
+    let caret_pad = " ".repeat(col.saturating_sub(1));
+
+    format!("{} |\n{} | {}\n{} | {}^", pad, gutter, text, pad, caret_pad)
+}
@@ -0,0 +1,117 @@
+use crate::error::CompileError;
+use crate::lexer::{Lexer, normalize_line_endings, string_literal_len, tokens_to_source};
+use crate::parser::Parser;
+use crate::token::{Token, TokenWithPos};
+
+/// Reprints a program as canonical source, for `umjunsik --fmt`.
+///
+/// Keywords are written in their Hangul form, padding spaces (at the ends of a
+/// line or `~` segment, and around a `식` expression) are dropped, runs of
+/// spaces collapse to one multiply, and tabs disappear. Comments and blank
+/// lines are kept, so every statement stays on its line. Lines joined with `~`
+/// are split one statement per line only when nothing jumps with `준`, since
+/// splitting renumbers the lines gotos refer to.
+///
+/// The source must parse. Formatting formatted source returns it unchanged.
+pub fn format_source(source: &str) -> Result<String, CompileError> {
+    let source = normalize_line_endings(source);
+    let tokens = Lexer::new(&source).tokenize()?;
+    Parser::new(tokens.clone()).parse()?;
+
+    let split_segments = !tokens.iter().any(|t| matches!(t.token, Token::Joon));
+    let tokens = drop_padding(tokens);
+
+    let mut lines = Vec::new();
+    for (idx, text) in source.lines().enumerate() {
+        let line_tokens: Vec<TokenWithPos> = tokens
+            .iter()
+            .filter(|t| t.line == idx + 1 && !matches!(t.token, Token::Newline))
+            .cloned()
+            .collect();
+        let line_tokens = if split_segments { split_segments_of(line_tokens) } else { line_tokens };
+        let mut code = tokens_to_source(&line_tokens);
+        // The lexer skips comments, so they're carried over from the source text
+        match comment_start(text) {
+            Some(start) if code.is_empty() => code = text[start..].to_string(),
+            Some(start) => code = format!("{} {}", code, &text[start..]),
+            None => {},
+        }
+        lines.push(code);
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    Ok(output)
+}
+
+// Byte offset of the `#` starting a comment, skipping any inside string literals
+fn comment_start(text: &str) -> Option<usize> {
+    let mut idx = 0;
+    while let Some(ch) = text[idx..].chars().next() {
+        match ch {
+            '#' => return Some(idx),
+            '"' => idx += string_literal_len(&text[idx..]),
+            _ => idx += ch.len_utf8(),
+        }
+    }
+    None
+}
+
+// Turn each `~` into a line break, dropping empty segments. A `~` right after a
+// conditional's `?` or `:` stays, since a line break there would open a block,
+// and so does every `~` of a line holding a `??` loop, whose body is the rest of it.
+fn split_segments_of(tokens: Vec<TokenWithPos>) -> Vec<TokenWithPos> {
+    let is_loop = tokens
+        .windows(2)
+        .any(|pair| matches!((&pair[0].token, &pair[1].token), (Token::Question, Token::Question)));
+    if is_loop {
+        return tokens;
+    }
+    let mut split: Vec<TokenWithPos> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if matches!(token.token, Token::Tilde) {
+            match split.last().map(|prev| &prev.token) {
+                Some(Token::Question | Token::Colon) => {},
+                None | Some(Token::Newline) => continue,
+                Some(_) => {
+                    split.push(TokenWithPos {
+                        token: Token::Newline,
+                        ..token
+                    });
+                    continue;
+                },
+            }
+        }
+        split.push(token);
+    }
+    if matches!(split.last().map(|last| &last.token), Some(Token::Newline)) {
+        split.pop();
+    }
+    split
+}
+
+// Drop the spaces that only pad: repeats, those at the ends of a line or `~`
+// segment, after `식`, and before the `!`/`ㅋ` that ends a print
+fn drop_padding(tokens: Vec<TokenWithPos>) -> Vec<TokenWithPos> {
+    let mut kept: Vec<TokenWithPos> = Vec::with_capacity(tokens.len());
+    for (idx, token) in tokens.iter().enumerate() {
+        if matches!(token.token, Token::Space) {
+            let after_padding = match kept.last() {
+                None => true,
+                Some(prev) => matches!(prev.token, Token::Space | Token::Newline | Token::Tilde | Token::Sik),
+            };
+            let before_padding = match tokens[idx + 1..].iter().find(|t| !matches!(t.token, Token::Space)) {
+                None => true,
+                Some(next) => matches!(
+                    next.token,
+                    Token::Newline | Token::Tilde | Token::EOF | Token::Exclamation | Token::Kek
+                ),
+            };
+            if after_padding || before_padding {
+                continue;
+            }
+        }
+        kept.push(token.clone());
+    }
+    kept
+}
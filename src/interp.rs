@@ -0,0 +1,308 @@
+use crate::ast::{Expr, Program, Statement};
+use crate::lower::lower_loops;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+// Control flow result of executing one statement
+enum Flow {
+    Next,
+    Goto(usize),
+    Return(i64),
+}
+
+// Why execution stopped before the program returned
+enum Halt {
+    // One of the generated code's traps: it prints the message and exits with status 1
+    Trap(&'static str),
+    Error(String),
+}
+
+impl From<String> for Halt {
+    fn from(message: String) -> Self {
+        Halt::Error(message)
+    }
+}
+
+struct Interpreter<'a> {
+    vars: HashMap<usize, i64>,
+    checked: bool,
+    truthy_conditionals: bool,
+    max_lines: Option<u64>,
+    stdin: &'a mut dyn Read,
+    stdout: &'a mut dyn Write,
+}
+
+/// Interpreter options matching the `CodeGenerator` options that change what
+/// a program does when it runs.
+#[derive(Debug, Default, Clone)]
+pub struct InterpretOptions {
+    checked: bool,
+    truthy_conditionals: bool,
+    max_lines: Option<u64>,
+}
+
+impl InterpretOptions {
+    /// Traps on i64 overflow like `CodeGeneratorBuilder::checked` code: prints
+    /// `arithmetic overflow` and exits with status 1 instead of wrapping.
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Runs a `동탄` body when its expression is nonzero instead of zero, like
+    /// `CodeGeneratorBuilder::truthy_conditionals`.
+    pub fn truthy_conditionals(mut self, truthy_conditionals: bool) -> Self {
+        self.truthy_conditionals = truthy_conditionals;
+        self
+    }
+
+    /// Counts statements as they run, like `CodeGeneratorBuilder::max_lines`
+    /// code: once more than `max_lines` have run it prints `line limit
+    /// exceeded` and exits with status 1.
+    pub fn max_lines(mut self, max_lines: Option<u64>) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    /// Executes `program` with these options; see `interpret`.
+    pub fn interpret(&self, program: &Program, stdin: &mut dyn Read, stdout: &mut dyn Write) -> Result<i64, String> {
+        let mut interp = Interpreter {
+            vars: HashMap::new(),
+            checked: self.checked,
+            truthy_conditionals: self.truthy_conditionals,
+            max_lines: self.max_lines,
+            stdin,
+            stdout,
+        };
+        let result = match interp.run(&lower_loops(program)) {
+            Ok(value) => Ok(value),
+            Err(Halt::Trap(message)) => {
+                writeln!(interp.stdout, "{}", message).map_err(|err| err.to_string())?;
+                Ok(1)
+            },
+            Err(Halt::Error(message)) => Err(message),
+        };
+        interp.stdout.flush().map_err(|err| err.to_string())?;
+        result
+    }
+}
+
+/// Executes a program directly, without Lamina or a linker.
+///
+/// Mirrors the semantics of the generated code: a goto to line N resumes at the
+/// first statement on or after line N (empty lines fall through), `식!` prints a
+/// number followed by a newline, and reading past the end of stdin yields -1.
+/// Returns the program's exit value. `InterpretOptions` runs it like code
+/// generated with other options.
+pub fn interpret(program: &Program, stdin: &mut dyn Read, stdout: &mut dyn Write) -> Result<i64, String> {
+    InterpretOptions::default().interpret(program, stdin, stdout)
+}
+
+impl Interpreter<'_> {
+    fn run(&mut self, program: &Program) -> Result<i64, Halt> {
+        let statements = &program.statements;
+        let mut pc = 0;
+        let mut steps: u64 = 0;
+
+        while pc < statements.len() {
+            // Counted per statement as it starts, like the generated step count
+            steps += 1;
+            if self.max_lines.is_some_and(|max_lines| steps > max_lines) {
+                return Err(Halt::Trap("line limit exceeded"));
+            }
+            match self.exec_statement(&statements[pc].0)? {
+                Flow::Next => pc += 1,
+                Flow::Goto(line) => {
+                    // Jumping past the last line ends the program
+                    match statements.iter().position(|(_, line_num)| *line_num >= line) {
+                        Some(target) => pc = target,
+                        None => return Ok(0),
+                    }
+                },
+                Flow::Return(value) => return Ok(value),
+            }
+        }
+
+        Ok(0)
+    }
+
+    fn exec_statement(&mut self, stmt: &Statement) -> Result<Flow, Halt> {
+        match stmt {
+            Statement::Assign { var_index, value } => {
+                let value = self.eval_expr(value)?;
+                self.vars.insert(*var_index, value);
+                Ok(Flow::Next)
+            },
+            Statement::Input { var_index } => {
+                let value = self.read_int()?;
+                self.vars.insert(*var_index, value);
+                Ok(Flow::Next)
+            },
+            Statement::InputMany { var_index, count } => {
+                for var_index in *var_index..var_index + count {
+                    let value = self.read_int()?;
+                    self.vars.insert(var_index, value);
+                }
+                Ok(Flow::Next)
+            },
+            Statement::InputChar { var_index } => {
+                let value = self.read_byte()?;
+                self.vars.insert(*var_index, value);
+                Ok(Flow::Next)
+            },
+            Statement::PrintNum(expr) => {
+                let value = self.eval_expr(expr)?;
+                writeln!(self.stdout, "{}", value).map_err(|err| err.to_string())?;
+                Ok(Flow::Next)
+            },
+            Statement::PrintNumRadix { expr, radix } => {
+                let value = self.eval_expr(expr)?;
+                writeln!(self.stdout, "{}", format_radix(value, *radix)).map_err(|err| err.to_string())?;
+                Ok(Flow::Next)
+            },
+            Statement::PrintChar(expr) => {
+                let value = self.eval_expr(expr)?;
+                self.write_byte(value as u8)?;
+                Ok(Flow::Next)
+            },
+            Statement::PrintNewline => {
+                self.write_byte(b'\n')?;
+                Ok(Flow::Next)
+            },
+            Statement::PrintStr(text) => {
+                for byte in text.bytes() {
+                    self.write_byte(byte)?;
+                }
+                Ok(Flow::Next)
+            },
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                // Body runs when the condition is zero (nonzero if truthy), else body otherwise
+                let takes_body = (self.eval_expr(condition)? == 0) != self.truthy_conditionals;
+                let branch = if takes_body { body } else { else_body };
+                for s in branch {
+                    match self.exec_statement(s)? {
+                        Flow::Next => {},
+                        flow => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Next)
+            },
+            Statement::While { .. } => unreachable!("loops are lowered before interpreting"),
+            Statement::Goto { target, .. } => Ok(Flow::Goto(*target)),
+            Statement::GotoDynamic(expr) => {
+                // Targets below line 1 end the program, like the generated code
+                let line = self.eval_expr(expr)?;
+                if line < 1 {
+                    Ok(Flow::Return(0))
+                } else {
+                    Ok(Flow::Goto(line as usize))
+                }
+            },
+            Statement::Return(expr) => Ok(Flow::Return(self.eval_expr(expr)?)),
+            Statement::Eval(expr) => {
+                self.eval_expr(expr)?;
+                Ok(Flow::Next)
+            },
+        }
+    }
+
+    // Operands are evaluated left to right, which orders the reads of 식?
+    fn eval_expr(&mut self, expr: &Expr) -> Result<i64, Halt> {
+        match expr {
+            Expr::Number(n) => Ok(*n),
+            Expr::Var(index) => Ok(self.vars.get(index).copied().unwrap_or(0)),
+            Expr::InputNum => Ok(self.read_int()?),
+            Expr::Add(left, right) => {
+                let (left, right) = (self.eval_expr(left)?, self.eval_expr(right)?);
+                self.arith(left.checked_add(right), left.wrapping_add(right))
+            },
+            Expr::Sub(left, right) => {
+                let (left, right) = (self.eval_expr(left)?, self.eval_expr(right)?);
+                self.arith(left.checked_sub(right), left.wrapping_sub(right))
+            },
+            Expr::Mul(left, right) => {
+                let (left, right) = (self.eval_expr(left)?, self.eval_expr(right)?);
+                self.arith(left.checked_mul(right), left.wrapping_mul(right))
+            },
+            Expr::Mod(left, right) => {
+                let left = self.eval_expr(left)?;
+                let right = self.eval_expr(right)?;
+                if right == 0 {
+                    return Err(Halt::Error("Modulo by zero".to_string()));
+                }
+                // i64::MIN % -1 overflows the division it is computed from
+                self.arith(left.checked_rem(right), left.wrapping_rem(right))
+            },
+            Expr::Pow(base, exp) => {
+                let base = self.eval_expr(base)?;
+                self.arith(base.checked_pow(*exp), base.wrapping_pow(*exp))
+            },
+            Expr::Neg(operand) => {
+                let operand = self.eval_expr(operand)?;
+                self.arith(operand.checked_neg(), operand.wrapping_neg())
+            },
+            Expr::Eq(left, right) => Ok((self.eval_expr(left)? == self.eval_expr(right)?) as i64),
+            Expr::Lt(left, right) => Ok((self.eval_expr(left)? < self.eval_expr(right)?) as i64),
+            Expr::Gt(left, right) => Ok((self.eval_expr(left)? > self.eval_expr(right)?) as i64),
+        }
+    }
+
+    // The wrapped result, or in checked mode the overflow trap when there is no exact one
+    fn arith(&self, exact: Option<i64>, wrapped: i64) -> Result<i64, Halt> {
+        match exact {
+            None if self.checked => Err(Halt::Trap("arithmetic overflow")),
+            _ => Ok(wrapped),
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<i64, String> {
+        let mut buf = [0u8; 1];
+        match self.stdin.read(&mut buf) {
+            Ok(0) => Ok(-1),
+            Ok(_) => Ok(buf[0] as i64),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        self.stdout.write_all(&[byte]).map_err(|err| err.to_string())
+    }
+
+    // Same algorithm as the generated input reader: skip spaces/newlines,
+    // then accumulate decimal digits until the first non-digit byte
+    fn read_int(&mut self) -> Result<i64, String> {
+        let mut byte = self.read_byte()?;
+        while byte == b' ' as i64 || byte == b'\n' as i64 {
+            byte = self.read_byte()?;
+        }
+
+        let mut acc: i64 = 0;
+        while (b'0' as i64..=b'9' as i64).contains(&byte) {
+            acc = acc.wrapping_mul(10).wrapping_add(byte - b'0' as i64);
+            byte = self.read_byte()?;
+        }
+
+        Ok(acc)
+    }
+}
+
+// Sign and magnitude with lowercase digits, like the generated digit loop
+fn format_radix(value: i64, radix: u32) -> String {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        digits.push(b"0123456789abcdef"[(magnitude % radix as u64) as usize] as char);
+        magnitude /= radix as u64;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
@@ -1,22 +1,206 @@
+use crate::error::CompileError;
 use crate::token::{Token, TokenWithPos};
+use unicode_normalization::UnicodeNormalization;
 
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
     line: usize,
     col: usize,
+    tab_as_space: bool,
+    ideographic_space_as_space: bool,
+}
+
+/// Turns CRLF and bare CR (old Mac) line endings into LF so every style counts
+/// lines the same.
+pub fn normalize_line_endings(input: &str) -> String {
+    input.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// ASCII spellings accepted by `expand_ascii_aliases`. None is a prefix of
+/// another, so a word made of them splits into aliases only one way.
+pub const ASCII_ALIASES: &[(&str, &str)] = &[
+    ("return", "화이팅"),
+    ("start", "어떻게"),
+    ("print", "식"),
+    ("goto", "준"),
+    ("end", "이 사람이름이냐ㅋㅋ"),
+    ("if", "동탄"),
+    ("a", "어"),
+    ("m", "엄"),
+    ("k", "ㅋ"),
+];
+
+/// Rewrites ASCII aliases (see `ASCII_ALIASES`) into their Hangul keywords, so
+/// the result lexes to exactly the tokens of the equivalent Hangul program.
+/// Aliases may run together (`ifa` is `동탄어`), but a word only expands if it
+/// is made of aliases from end to end, so the `a` in `data` is left alone.
+/// Comments and anything that isn't an alias are copied unchanged; line
+/// breaks are preserved, so line numbers still match the original.
+pub fn expand_ascii_aliases(source: &str) -> String {
+    let mut output = String::with_capacity(source.len() * 2);
+    let mut rest = source;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch == '#' {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if ch == '"' {
+            let end = string_literal_len(rest);
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if is_alias_word_char(ch) {
+            let end = rest.find(|c| !is_alias_word_char(c)).unwrap_or(rest.len());
+            let word = &rest[..end];
+            output.push_str(&expand_alias_word(word).unwrap_or_else(|| word.to_string()));
+            rest = &rest[end..];
+            continue;
+        }
+        output.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    output
+}
+
+fn is_alias_word_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+// `word` spelled as Hangul, or `None` if some part of it isn't an alias
+fn expand_alias_word(word: &str) -> Option<String> {
+    let mut hangul = String::new();
+    let mut rest = word;
+    while !rest.is_empty() {
+        let (alias, keyword) = ASCII_ALIASES.iter().find(|(alias, _)| rest.starts_with(alias))?;
+        hangul.push_str(keyword);
+        rest = &rest[alias.len()..];
+    }
+    Some(hangul)
+}
+
+/// Length in bytes of the string literal `text` starts with, including both
+/// quotes, or the rest of the line if it is unterminated.
+pub(crate) fn string_literal_len(text: &str) -> usize {
+    let mut escaped = false;
+    for (idx, ch) in text.char_indices().skip(1) {
+        match ch {
+            '\n' => return idx,
+            '"' if !escaped => return idx + 1,
+            _ => escaped = ch == '\\' && !escaped,
+        }
+    }
+    text.len()
+}
+
+/// Writes tokens back out as canonical Umjunsik source: Hangul keywords,
+/// `@` literals for numbers, and exactly the spaces and newlines in the token
+/// stream (so tabs, comments and ASCII aliases are gone).
+///
+/// Re-tokenizing the result yields the same tokens for any stream produced by
+/// the lexer. Hand-built streams may not survive: adjacent `Eo`/`Eom` tokens or
+/// dot runs, for example, lex back as one merged token.
+pub fn tokens_to_source(tokens: &[TokenWithPos]) -> String {
+    let mut output = String::new();
+    for token in tokens {
+        match &token.token {
+            Token::Eotteohke => output.push_str("어떻게"),
+            Token::IEotteonSaram => output.push_str("이 사람이름이냐ㅋㅋ"),
+            Token::Eom(eo_count) => {
+                output.push_str(&"어".repeat(*eo_count));
+                output.push('엄');
+            },
+            Token::Eo(count) => output.push_str(&"어".repeat(*count)),
+            Token::Joon => output.push('준'),
+            Token::Sik => output.push('식'),
+            Token::Dongtan => output.push_str("동탄"),
+            Token::Hwaiting => output.push_str("화이팅"),
+            Token::Dot(run) => output.push_str(&".".repeat(*run)),
+            Token::Comma(run) => output.push_str(&",".repeat(*run)),
+            Token::Space => output.push(' '),
+            Token::Tilde => output.push('~'),
+            Token::Percent => output.push('%'),
+            Token::Colon => output.push(':'),
+            Token::Semicolon => output.push(';'),
+            Token::LParen => output.push('('),
+            Token::RParen => output.push(')'),
+            Token::Caret => output.push('^'),
+            Token::Minus => output.push('-'),
+            Token::Equals => output.push('='),
+            Token::Less => output.push('<'),
+            Token::Greater => output.push('>'),
+            Token::Dollar => output.push('$'),
+            Token::Question => output.push('?'),
+            Token::Exclamation => output.push('!'),
+            Token::Kek => output.push('ㅋ'),
+            Token::Number(value) => output.push_str(&format!("@{}", value)),
+            Token::Str(text) => {
+                output.push('"');
+                for ch in text.chars() {
+                    match ch {
+                        '\n' => output.push_str("\\n"),
+                        '\t' => output.push_str("\\t"),
+                        '"' | '\\' => {
+                            output.push('\\');
+                            output.push(ch);
+                        },
+                        _ => output.push(ch),
+                    }
+                }
+                output.push('"');
+            },
+            Token::Newline => output.push('\n'),
+            Token::EOF => {},
+        }
+    }
+    output
 }
 
 impl Lexer {
     pub fn new(input: &str) -> Self {
+        // Compose decomposed (NFD) Hangul so jamo sequences match the keyword tables.
+        // Positions are tracked over the normalized characters.
         Lexer {
-            input: input.chars().collect(),
+            input: normalize_line_endings(input).nfc().collect(),
             position: 0,
             line: 1,
             col: 1,
+            tab_as_space: false,
+            ideographic_space_as_space: false,
         }
     }
 
+    /// Accepts the ASCII aliases in `ASCII_ALIASES` in place of Hangul keywords.
+    pub fn with_ascii_aliases(mut self, enabled: bool) -> Self {
+        if enabled {
+            let source: String = self.input.iter().collect();
+            self.input = expand_ascii_aliases(&source).nfc().collect();
+        }
+        self
+    }
+
+    /// Lexes `\t` as a multiplying space instead of skipping it.
+    pub fn with_tab_as_space(mut self, enabled: bool) -> Self {
+        self.tab_as_space = enabled;
+        self
+    }
+
+    /// Lexes the full-width space U+3000 as a multiplying space instead of
+    /// rejecting it.
+    pub fn with_ideographic_space_as_space(mut self, enabled: bool) -> Self {
+        self.ideographic_space_as_space = enabled;
+        self
+    }
+
+    fn is_space(&self, ch: char) -> bool {
+        ch == ' ' || (ch == '\t' && self.tab_as_space) || (ch == '\u{3000}' && self.ideographic_space_as_space)
+    }
+
     fn current_char(&self) -> Option<char> {
         if self.position < self.input.len() {
             Some(self.input[self.position])
@@ -41,9 +225,19 @@ impl Lexer {
         }
     }
 
+    fn skip_comment(&mut self) {
+        // Comments run from '#' to the end of the line; the newline itself is kept
+        while let Some(ch) = self.current_char() {
+            if ch == '\n' {
+                break;
+            }
+            self.advance();
+        }
+    }
+
     fn skip_whitespace_except_newline_and_space(&mut self) {
         while let Some(ch) = self.current_char() {
-            if ch == '\t' || ch == '\r' {
+            if ch == '\t' && !self.tab_as_space {
                 self.advance();
             } else {
                 break;
@@ -51,107 +245,327 @@ impl Lexer {
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<TokenWithPos>, String> {
-        let mut tokens = Vec::new();
+    pub fn tokenize(&mut self) -> Result<Vec<TokenWithPos>, CompileError> {
+        trace_span!("tokenize");
+        self.token_iter().collect()
+    }
+
+    /// Lexes lazily, one token per call to `next`, instead of collecting the
+    /// whole program like `tokenize` does. The iterator ends after yielding
+    /// `Token::EOF` or the first error.
+    pub fn token_iter(&mut self) -> impl Iterator<Item = Result<TokenWithPos, CompileError>> + '_ {
+        let mut finished = false;
+        std::iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+            let result = self.next_token();
+            finished = !matches!(&result, Ok(TokenWithPos { token, .. }) if *token != Token::EOF);
+            Some(result)
+        })
+    }
 
+    fn next_token(&mut self) -> Result<TokenWithPos, CompileError> {
         loop {
+            let start = self.position;
             self.skip_whitespace_except_newline_and_space();
 
             let line = self.line;
             let col = self.col;
 
-            match self.current_char() {
-                None => {
-                    tokens.push(TokenWithPos {
-                        token: Token::EOF,
-                        line,
-                        col,
-                    });
-                    break;
+            let token = match self.current_char() {
+                None => Some(TokenWithPos {
+                    token: Token::EOF,
+                    line,
+                    col,
+                }),
+                Some(_) if self.spaces_before_comment() => {
+                    // Spaces separating a statement from its comment are not multiplication
+                    while self.current_char().is_some_and(|ch| ch != '#') {
+                        self.advance();
+                    }
+                    None
                 },
-                Some(' ') => {
+                Some(ch) if self.is_space(ch) => {
                     self.advance();
-                    tokens.push(TokenWithPos {
+                    Some(TokenWithPos {
                         token: Token::Space,
                         line,
                         col,
-                    });
+                    })
                 },
                 Some('\n') => {
                     self.advance();
-                    tokens.push(TokenWithPos {
+                    Some(TokenWithPos {
                         token: Token::Newline,
                         line,
                         col,
-                    });
+                    })
+                },
+                Some('#') => {
+                    self.skip_comment();
+                    None
                 },
                 Some('~') => {
                     self.advance();
-                    tokens.push(TokenWithPos {
+                    Some(TokenWithPos {
                         token: Token::Tilde,
                         line,
                         col,
-                    });
+                    })
                 },
-                Some('.') => {
+                Some('%') => {
                     self.advance();
-                    tokens.push(TokenWithPos {
-                        token: Token::Dot,
+                    Some(TokenWithPos {
+                        token: Token::Percent,
                         line,
                         col,
-                    });
+                    })
                 },
-                Some(',') => {
+                Some('=') => {
                     self.advance();
-                    tokens.push(TokenWithPos {
-                        token: Token::Comma,
+                    Some(TokenWithPos {
+                        token: Token::Equals,
                         line,
                         col,
-                    });
+                    })
+                },
+                Some('<') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::Less,
+                        line,
+                        col,
+                    })
+                },
+                Some('>') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::Greater,
+                        line,
+                        col,
+                    })
+                },
+                Some('$') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::Dollar,
+                        line,
+                        col,
+                    })
+                },
+                Some(';') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::Semicolon,
+                        line,
+                        col,
+                    })
+                },
+                Some(':') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::Colon,
+                        line,
+                        col,
+                    })
+                },
+                Some('@') => {
+                    // Compact literal: @ followed by decimal digits, e.g. @1000000
+                    self.advance();
+                    let mut digits = String::new();
+                    while let Some(ch) = self.current_char().filter(char::is_ascii_digit) {
+                        digits.push(ch);
+                        self.advance();
+                    }
+                    if digits.is_empty() {
+                        return Err(CompileError::Lex {
+                            line,
+                            col,
+                            message: "Expected digits after '@'".to_string(),
+                        });
+                    }
+                    let value = digits.parse::<i64>().map_err(|_| CompileError::Lex {
+                        line,
+                        col,
+                        message: format!("Number literal @{} does not fit in i64", digits),
+                    })?;
+                    Some(TokenWithPos {
+                        token: Token::Number(value),
+                        line,
+                        col,
+                    })
+                },
+                Some('"') => {
+                    let text = self.read_string_literal(line, col)?;
+                    Some(TokenWithPos {
+                        token: Token::Str(text),
+                        line,
+                        col,
+                    })
+                },
+                Some('^') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::Caret,
+                        line,
+                        col,
+                    })
+                },
+                Some('-') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::Minus,
+                        line,
+                        col,
+                    })
+                },
+                Some('(') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::LParen,
+                        line,
+                        col,
+                    })
+                },
+                Some(')') => {
+                    self.advance();
+                    Some(TokenWithPos {
+                        token: Token::RParen,
+                        line,
+                        col,
+                    })
+                },
+                Some(ch @ ('.' | ',')) => {
+                    // One token per run, so huge literals don't cost a token per character
+                    let mut count = 0;
+                    while self.current_char() == Some(ch) {
+                        count += 1;
+                        self.advance();
+                    }
+                    let token = if ch == '.' { Token::Dot(count) } else { Token::Comma(count) };
+                    Some(TokenWithPos { token, line, col })
                 },
                 Some('?') => {
                     self.advance();
-                    tokens.push(TokenWithPos {
+                    Some(TokenWithPos {
                         token: Token::Question,
                         line,
                         col,
-                    });
+                    })
                 },
                 Some('!') => {
                     self.advance();
-                    tokens.push(TokenWithPos {
+                    Some(TokenWithPos {
                         token: Token::Exclamation,
                         line,
                         col,
-                    });
+                    })
                 },
                 Some('ㅋ') => {
                     self.advance();
-                    tokens.push(TokenWithPos {
+                    Some(TokenWithPos {
                         token: Token::Kek,
                         line,
                         col,
-                    });
+                    })
                 },
                 Some(ch) if self.is_hangul_start(ch) => {
-                    let keyword = self.read_hangul_keyword()?;
-                    let token = self.match_keyword(&keyword)?;
-                    tokens.push(TokenWithPos { token, line, col });
+                    let keyword = self.read_hangul_keyword();
+                    let token = self.match_keyword(&keyword).map_err(|message| CompileError::Lex {
+                        line,
+                        col,
+                        message,
+                    })?;
+                    Some(TokenWithPos { token, line, col })
                 },
                 Some(ch) => {
-                    return Err(format!("Unexpected character '{}' at line {}, col {}", ch, line, col));
+                    return Err(CompileError::Lex {
+                        line,
+                        col,
+                        message: format!("Unexpected character '{}'", ch),
+                    });
                 },
+            };
+
+            // Comments and skipped characters produce no token
+            trace_event!(line, col, token = ?token.as_ref().map(|t| &t.token), "lexed");
+
+            // Every iteration must consume input, or arbitrary text could hang the lexer
+            debug_assert!(
+                self.position > start || matches!(token, Some(TokenWithPos { token: Token::EOF, .. })),
+                "lexer made no progress at line {}, col {}",
+                line,
+                col
+            );
+
+            if let Some(token) = token {
+                return Ok(token);
             }
         }
+    }
+
+    // Whether the spaces (and skipped tabs) from here run straight into a `#` comment
+    fn spaces_before_comment(&self) -> bool {
+        let rest = &self.input[self.position..];
+        let next = rest.iter().find(|&&ch| !(self.is_space(ch) || (ch == '\t' && !self.tab_as_space)));
+        next == Some(&'#') && self.is_space(rest[0])
+    }
 
-        Ok(tokens)
+    // Everything up to the closing quote is literal text: keywords, spaces and
+    // `#` mean nothing inside. Escapes are \n, \t, \\ and \".
+    fn read_string_literal(&mut self, line: usize, col: usize) -> Result<String, CompileError> {
+        self.advance(); // skip opening quote
+        let mut text = String::new();
+        loop {
+            match self.current_char() {
+                None | Some('\n') => {
+                    return Err(CompileError::Lex {
+                        line,
+                        col,
+                        message: "Unterminated string literal".to_string(),
+                    });
+                },
+                Some('"') => {
+                    self.advance();
+                    return Ok(text);
+                },
+                Some('\\') => {
+                    let (esc_line, esc_col) = (self.line, self.col);
+                    self.advance();
+                    let ch = match self.current_char() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some(ch @ ('\\' | '"')) => ch,
+                        other => {
+                            return Err(CompileError::Lex {
+                                line: esc_line,
+                                col: esc_col,
+                                message: format!(
+                                    "Unknown escape '\\{}' in string literal",
+                                    other.map(String::from).unwrap_or_default()
+                                ),
+                            });
+                        },
+                    };
+                    self.advance();
+                    text.push(ch);
+                },
+                Some(ch) => {
+                    self.advance();
+                    text.push(ch);
+                },
+            }
+        }
     }
 
     fn is_hangul_start(&self, ch: char) -> bool {
         matches!(ch, '어' | '엄' | '준' | '식' | '동' | '화' | '이')
     }
 
-    fn read_hangul_keyword(&mut self) -> Result<String, String> {
+    // Only called on an `is_hangul_start` character, and every path below consumes
+    // at least that character, so the caller always makes progress
+    fn read_hangul_keyword(&mut self) -> String {
         let mut keyword = String::new();
 
         // Special handling for "이 사람이름이냐ㅋㅋ"
@@ -159,42 +573,11 @@ impl Lexer {
             keyword.push('이');
             self.advance();
 
-            // Check if it's the start of "이 사람이름이냐ㅋㅋ"
-            if self.current_char() == Some(' ') {
-                // Try to read the full end marker
-                let saved_pos = self.position;
-                let saved_line = self.line;
-                let saved_col = self.col;
-
-                self.advance(); // skip space
-
-                // Try to match "사람이름이냐"
-                let mut temp = String::new();
-                while let Some(ch) = self.current_char() {
-                    if self.is_hangul_char(ch) {
-                        temp.push(ch);
-                        self.advance();
-                    } else {
-                        break;
-                    }
-                }
-
-                if temp.contains("사람이름이냐") {
-                    // This is the end marker, consume any trailing ㅋs
-                    while self.current_char() == Some('ㅋ') {
-                        self.advance();
-                    }
-                    return Ok("이 사람이름이냐".to_string());
-                } else {
-                    // Not the end marker, restore position
-                    self.position = saved_pos;
-                    self.line = saved_line;
-                    self.col = saved_col;
-                    return Ok(keyword);
-                }
+            if self.read_end_marker_tail() {
+                return "이 사람이름이냐".to_string();
             }
 
-            // Continue reading if not followed by space
+            // Continue reading if not the end marker
             while let Some(ch) = self.current_char() {
                 if self.is_hangul_char(ch) {
                     keyword.push(ch);
@@ -203,7 +586,7 @@ impl Lexer {
                     break;
                 }
             }
-            return Ok(keyword);
+            return keyword;
         }
 
         // Read the first character
@@ -215,6 +598,15 @@ impl Lexer {
         // Check if we have a complete keyword
         // Keywords: 어떻게, 준, 식, 동탄, 화이팅, 엄, 어
         match keyword.as_str() {
+            // An assignment token is the 어s before 엄 plus the 엄 itself, and stops
+            // there; any 어s after 엄 are read as the next token, a variable reference.
+            // So with n 어s before 엄 the target is var n+1, and n 어s alone read var n:
+            //
+            //   엄        -> Eom(0)          var1 = 0
+            //   어엄      -> Eom(1)          var2 = 0
+            //   어어엄    -> Eom(2)          var3 = 0
+            //   어엄어    -> Eom(1), Eo(1)   var2 = var1
+            //   어어엄어어 -> Eom(2), Eo(2)   var3 = var2
             "어" => {
                 // Could be part of "어떻게" or standalone "어" or repeated "어어어..." or "어엄" or "어어엄"
                 if self.current_char() == Some('떻') {
@@ -284,7 +676,43 @@ impl Lexer {
             },
         }
 
-        Ok(keyword)
+        keyword
+    }
+
+    // With the leading 이 consumed, matches the rest of the end marker: any
+    // spaces or tabs, exactly 사람이름이냐, then any number of ㅋs. The marker must
+    // end the word, so e.g. 이 사람이름이냐고 is not taken as the end. On a
+    // mismatch the position is restored and nothing is consumed.
+    fn read_end_marker_tail(&mut self) -> bool {
+        let saved_pos = self.position;
+        let saved_line = self.line;
+        let saved_col = self.col;
+
+        while matches!(self.current_char(), Some(' ' | '\t')) {
+            self.advance();
+        }
+
+        let mut matched = true;
+        for expected in "사람이름이냐".chars() {
+            if self.current_char() != Some(expected) {
+                matched = false;
+                break;
+            }
+            self.advance();
+        }
+        if matched {
+            while self.current_char() == Some('ㅋ') {
+                self.advance();
+            }
+            matched = !self.current_char().is_some_and(|ch| self.is_hangul_char(ch));
+        }
+
+        if !matched {
+            self.position = saved_pos;
+            self.line = saved_line;
+            self.col = saved_col;
+        }
+        matched
     }
 
     fn is_hangul_char(&self, ch: char) -> bool {
@@ -329,7 +757,6 @@ impl Lexer {
             "어" => Ok(Token::Eo(1)),
             // Handle "이 사람이름이냐" (program end marker)
             "이 사람이름이냐" => Ok(Token::IEotteonSaram),
-            _ if keyword.contains("사람이름이냐") => Ok(Token::IEotteonSaram),
             _ => Err(format!("Unknown keyword: {}", keyword)),
         }
     }
@@ -1,25 +1,355 @@
+#[macro_use]
+mod trace;
+
 pub mod ast;
+pub mod c_backend;
 pub mod codegen;
+pub mod diff;
+pub mod dot;
+pub mod error;
+pub mod formatter;
+pub mod interp;
 pub mod lexer;
+pub mod lint;
+pub mod llvm;
+pub mod lower;
 pub mod parser;
+pub mod target;
 pub mod token;
+pub mod validate;
+pub mod visit;
+pub mod wasm;
 
-use codegen::CodeGenerator;
-use lexer::Lexer;
+use ast::{Program, Span, Statement};
+pub use c_backend::generate_c;
+use codegen::{CodeGenerator, CodeGeneratorBuilder};
+pub use dot::ast_to_dot;
+pub use error::{CompileError, render_diagnostic};
+pub use formatter::format_source;
+pub use interp::{InterpretOptions, interpret};
+pub use lexer::tokens_to_source;
+pub use lint::{Warning, lint};
+pub use llvm::generate_llvm_ir;
+pub use lower::lower_loops;
+pub use target::{Target, UnknownTarget};
+pub use validate::validate;
+pub use visit::Visitor;
+pub use wasm::generate_wat;
+use lexer::{Lexer, normalize_line_endings};
 use parser::Parser;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use token::TokenWithPos;
 
-pub fn compile_umjunsik(source: &str) -> Result<String, String> {
-    // Lexical analysis
+/// Size and timing figures for one compilation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompileStats {
+    pub source_lines: usize,
+    pub token_count: usize,
+    pub statement_count: usize,
+    pub ir_lines: usize,
+    pub lex_time: Duration,
+    pub parse_time: Duration,
+    pub codegen_time: Duration,
+}
+
+impl fmt::Display for CompileStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "source lines:  {}", self.source_lines)?;
+        writeln!(f, "tokens:        {}", self.token_count)?;
+        writeln!(f, "statements:    {}", self.statement_count)?;
+        writeln!(f, "IR lines:      {}", self.ir_lines)?;
+        writeln!(f, "lex time:      {:?}", self.lex_time)?;
+        writeln!(f, "parse time:    {:?}", self.parse_time)?;
+        write!(f, "codegen time:  {:?}", self.codegen_time)
+    }
+}
+
+/// Tokenizes Umjunsik source into tokens with their line/column positions.
+///
+/// This is a stable entry point; prefer it over using `lexer::Lexer` directly.
+pub fn tokenize(source: &str) -> Result<Vec<TokenWithPos>, CompileError> {
     let mut lexer = Lexer::new(source);
-    let tokens = lexer.tokenize()?;
+    lexer.tokenize()
+}
+
+/// Tokenizes and parses Umjunsik source into a `Program`.
+///
+/// This is a stable entry point; prefer it over using `parser::Parser` directly.
+pub fn parse(source: &str) -> Result<Program, CompileError> {
+    parse_with(source, false)
+}
+
+/// Like `parse`, but a source that ends without `이 사람이름이냐ㅋㅋ` is an
+/// error rather than a program flagged with `missing_end_marker`.
+pub fn parse_strict(source: &str) -> Result<Program, CompileError> {
+    parse_with(source, true)
+}
+
+fn parse_with(source: &str, strict: bool) -> Result<Program, CompileError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser::new(tokens).strict(strict);
+    parser.parse()
+}
+
+/// Parses several sources and merges them into one `Program` that runs them in order.
+///
+/// Each file's lines are offset by the line count of the files before it, so
+/// line N of the second file becomes line `lines(first) + N`. Constant goto
+/// targets are rebased the same way and must stay within their own file;
+/// computed targets (`준어`) address the merged line numbers. Error locations
+/// are reported as merged line numbers too, which match the sources joined
+/// end to end.
+pub fn parse_multi(sources: &[&str]) -> Result<Program, CompileError> {
+    parse_multi_with(sources, false)
+}
+
+/// Like `parse_multi`, but every file must end with `이 사람이름이냐ㅋㅋ`, as
+/// with `parse_strict`.
+pub fn parse_multi_strict(sources: &[&str]) -> Result<Program, CompileError> {
+    parse_multi_with(sources, true)
+}
+
+fn parse_multi_with(sources: &[&str], strict: bool) -> Result<Program, CompileError> {
+    let mut statements: Vec<(Statement, usize)> = Vec::new();
+    let mut spans = Vec::new();
+    let mut locals = BTreeMap::new();
+    let mut missing_end_marker = None;
+    let mut offset = 0;
+
+    for source in sources {
+        let mut program = parse_with(source, strict).map_err(|err| shift_error(err, offset))?;
+        missing_end_marker = missing_end_marker.or(program.missing_end_marker.map(|line| line + offset));
+
+        let max_line = program.statements.iter().map(|(_, line)| *line).max().unwrap_or(1);
+        for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+            CodeGenerator::check_goto_targets(stmt, max_line, program.span(idx))
+                .map_err(|err| shift_error(err, offset))?;
+        }
+
+        for (stmt, line) in &mut program.statements {
+            *line += offset;
+            offset_gotos(stmt, offset);
+        }
+        for span in &mut program.spans {
+            span.line += offset;
+        }
+
+        statements.append(&mut program.statements);
+        spans.append(&mut program.spans);
+        // Each file numbers its block-local variables from the same start. They
+        // never outlive their block, so two files sharing an index is harmless.
+        locals.append(&mut program.locals);
+        offset += normalize_line_endings(source).lines().count();
+    }
+
+    Ok(Program {
+        statements,
+        spans,
+        locals,
+        missing_end_marker,
+    })
+}
+
+// Moves an error reported against one file to its merged line number
+fn shift_error(err: CompileError, offset: usize) -> CompileError {
+    match err {
+        CompileError::Lex { line, col, message } => CompileError::Lex {
+            line: line + offset,
+            col,
+            message,
+        },
+        CompileError::Parse {
+            position,
+            line,
+            col,
+            message,
+        } => CompileError::Parse {
+            position,
+            line: line + offset,
+            col,
+            message,
+        },
+        CompileError::Codegen { span, message } => CompileError::Codegen {
+            span: span.map(|span| Span {
+                line: span.line + offset,
+                col: span.col,
+            }),
+            message,
+        },
+        CompileError::Io { .. } => err,
+    }
+}
+
+fn offset_gotos(stmt: &mut Statement, offset: usize) {
+    match stmt {
+        Statement::Goto { target, line } => {
+            *target += offset;
+            *line += offset;
+        },
+        Statement::Conditional { body, else_body, .. } => {
+            for s in body.iter_mut().chain(else_body) {
+                offset_gotos(s, offset);
+            }
+        },
+        // A loop jumps between its own lines, so those move with the file too
+        Statement::While { body, end_line, .. } => {
+            *end_line += offset;
+            for (s, span) in body {
+                span.line += offset;
+                offset_gotos(s, offset);
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Parses Umjunsik source and serializes the resulting `Program` as JSON.
+#[cfg(feature = "serde")]
+pub fn parse_to_json(source: &str) -> Result<String, CompileError> {
+    let program = parse(source)?;
+    serde_json::to_string(&program).map_err(|err| CompileError::Codegen {
+        span: None,
+        message: format!("Failed to serialize program: {}", err),
+    })
+}
+
+pub fn compile_umjunsik(source: &str) -> Result<String, CompileError> {
+    compile_with(source, false)
+}
+
+/// Reads the source file at `path` and compiles it like `compile_umjunsik`.
+pub fn compile_umjunsik_file(path: impl AsRef<Path>) -> Result<String, CompileError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|err| CompileError::Io {
+        message: format!("Error reading file '{}': {}", path.display(), err),
+    })?;
+    compile_umjunsik(&source)
+}
+
+/// Reads all of `reader` as UTF-8 source and compiles it like `compile_umjunsik`.
+pub fn compile_umjunsik_reader(mut reader: impl Read) -> Result<String, CompileError> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source).map_err(|err| CompileError::Io {
+        message: format!("Error reading source: {}", err),
+    })?;
+    compile_umjunsik(&source)
+}
+
+/// Like `compile_umjunsik`, but the generated code traps on i64 overflow
+/// instead of wrapping.
+pub fn compile_umjunsik_checked(source: &str) -> Result<String, CompileError> {
+    compile_with(source, true)
+}
+
+/// Like `compile_umjunsik`, but also reports sizes and per-stage timings.
+pub fn compile_umjunsik_with_stats(source: &str) -> Result<(String, CompileStats), CompileError> {
+    compile_instrumented(source, CodeGenerator::builder())
+}
+
+/// Like `compile_umjunsik_with_stats`, but generates code with the options
+/// collected in `options` (overflow checks, optimization level, ...).
+pub fn compile_umjunsik_with_options(
+    source: &str,
+    options: CodeGeneratorBuilder,
+) -> Result<(String, CompileStats), CompileError> {
+    compile_instrumented(source, options)
+}
+
+/// Compiles several sources as one program; see `parse_multi` for how they are merged.
+pub fn compile_umjunsik_multi(sources: &[&str]) -> Result<String, CompileError> {
+    let program = parse_multi(sources)?;
+    validate_first(&program)?;
+    CodeGenerator::new(false).generate(&program)
+}
+
+/// Assembles Lamina IR (as from `compile_umjunsik`) for the host target with
+/// Lamina's own backend, returning the assembly text. Linking it is left to
+/// the caller; the CLI links with the compiler `select_c_compiler` picks.
+pub fn ir_to_assembly(ir: &str) -> Result<Vec<u8>, CompileError> {
+    let mut assembly = Vec::new();
+    lamina::compile_lamina_ir_to_assembly(ir, &mut assembly).map_err(lamina_error)?;
+    Ok(assembly)
+}
+
+/// Like `ir_to_assembly`, but for `target` rather than the host.
+pub fn ir_to_assembly_for(ir: &str, target: Target) -> Result<Vec<u8>, CompileError> {
+    let mut assembly = Vec::new();
+    lamina::compile_lamina_ir_to_target_assembly(ir, &mut assembly, target.lamina_name()).map_err(lamina_error)?;
+    Ok(assembly)
+}
+
+fn lamina_error(err: impl fmt::Display) -> CompileError {
+    CompileError::Codegen {
+        span: None,
+        message: format!("Lamina compilation error: {}", err),
+    }
+}
+
+/// The Lamina target for the machine running this code, or the name Lamina
+/// detected if this crate has no `Target` for it.
+pub fn host_target() -> Result<Target, UnknownTarget> {
+    lamina::detect_host_architecture().parse()
+}
+
+/// C compilers probed, in order, to assemble and link the generated assembly.
+pub const C_COMPILER_CANDIDATES: [&str; 3] = ["clang", "cc", "gcc"];
+
+/// Picks the C compiler that assembles and links `--run` output: `explicit`
+/// (the `--cc` option) if given, then `env_cc` (the `CC` variable) if set and
+/// nonempty, then the first of `C_COMPILER_CANDIDATES` that `is_present`
+/// accepts. `None` means the probe found nothing.
+pub fn select_c_compiler(
+    explicit: Option<&str>,
+    env_cc: Option<&str>,
+    is_present: impl Fn(&str) -> bool,
+) -> Option<String> {
+    explicit
+        .or(env_cc.filter(|cc| !cc.is_empty()))
+        .or_else(|| C_COMPILER_CANDIDATES.into_iter().find(|cc| is_present(cc)))
+        .map(str::to_string)
+}
+
+// Validates `program`, reporting only the earliest error
+fn validate_first(program: &Program) -> Result<(), CompileError> {
+    validate(program).map_err(|errors| errors.into_iter().next().expect("validate reports at least one error"))
+}
+
+fn compile_with(source: &str, checked: bool) -> Result<String, CompileError> {
+    compile_instrumented(source, CodeGenerator::builder().checked(checked)).map(|(lamina_ir, _)| lamina_ir)
+}
+
+fn compile_instrumented(source: &str, options: CodeGeneratorBuilder) -> Result<(String, CompileStats), CompileError> {
+    // Lexical analysis
+    let start = Instant::now();
+    let tokens = tokenize(source)?;
+    let lex_time = start.elapsed();
+    let token_count = tokens.len();
 
     // Parsing
-    let mut parser = Parser::new(tokens);
-    let program = parser.parse()?;
+    let start = Instant::now();
+    let program = Parser::new(tokens).parse()?;
+    let parse_time = start.elapsed();
+    validate_first(&program)?;
 
     // Code generation
-    let mut codegen = CodeGenerator::new();
+    let start = Instant::now();
+    let mut codegen = options.build();
     let lamina_ir = codegen.generate(&program)?;
+    let codegen_time = start.elapsed();
 
-    Ok(lamina_ir)
+    let stats = CompileStats {
+        source_lines: normalize_line_endings(source).lines().count(),
+        token_count,
+        statement_count: program.statements.len(),
+        ir_lines: lamina_ir.lines().count(),
+        lex_time,
+        parse_time,
+        codegen_time,
+    };
+    Ok((lamina_ir, stats))
 }
@@ -0,0 +1,204 @@
+use crate::ast::{Expr, Program, Statement};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    // Variable is assigned (or read into) but never read
+    UnusedVariable { var_index: usize, line: usize },
+    // Conditional with neither a body nor an else part, so it does nothing
+    EmptyConditional { line: usize },
+    // Statement after a goto or return that nothing jumps to; codegen drops it
+    UnreachableStatement { line: usize },
+    // Source ends without 이 사람이름이냐ㅋㅋ after this line
+    MissingEndMarker { line: usize },
+}
+
+impl Warning {
+    /// Source line the warning points at.
+    pub fn line(&self) -> usize {
+        match self {
+            Warning::UnusedVariable { line, .. }
+            | Warning::EmptyConditional { line }
+            | Warning::UnreachableStatement { line }
+            | Warning::MissingEndMarker { line } => *line,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::UnusedVariable { var_index, line } => {
+                write!(f, "variable {} is assigned but never read at line {}", var_index, line)
+            },
+            Warning::EmptyConditional { line } => {
+                write!(f, "conditional with empty body has no effect at line {}", line)
+            },
+            Warning::UnreachableStatement { line } => {
+                write!(f, "statement is unreachable and was removed at line {}", line)
+            },
+            Warning::MissingEndMarker { line } => {
+                write!(f, "program missing 이 사람이름이냐ㅋㅋ end marker at line {}", line)
+            },
+        }
+    }
+}
+
+/// Runs static checks that don't prevent compilation and returns any warnings,
+/// ordered by source line.
+pub fn lint(program: &Program) -> Vec<Warning> {
+    let mut assigned = BTreeMap::new(); // var_index -> line of first assignment
+    let mut read = BTreeSet::new();
+    let mut empty_conditionals = Vec::new();
+
+    for (stmt, line) in &program.statements {
+        collect_from_statement(stmt, *line, &mut assigned, &mut read);
+        find_empty_conditionals(stmt, *line, &mut empty_conditionals);
+    }
+
+    let mut warnings: Vec<Warning> = assigned
+        .into_iter()
+        .filter(|(var_index, _)| !read.contains(var_index))
+        // A block-local variable is reported by the index written in the source
+        .map(|(var_index, line)| Warning::UnusedVariable {
+            var_index: program.locals.get(&var_index).copied().unwrap_or(var_index),
+            line,
+        })
+        .collect();
+    warnings.extend(empty_conditionals.into_iter().map(|line| Warning::EmptyConditional { line }));
+    let unreachable_lines: BTreeSet<usize> =
+        unreachable_statements(program).into_iter().map(|idx| program.line(idx)).collect();
+    warnings.extend(unreachable_lines.into_iter().map(|line| Warning::UnreachableStatement { line }));
+    if let Some(line) = program.missing_end_marker {
+        warnings.push(Warning::MissingEndMarker { line });
+    }
+    warnings.sort_by_key(Warning::line);
+    warnings
+}
+
+fn collect_from_statement(
+    stmt: &Statement,
+    line: usize,
+    assigned: &mut BTreeMap<usize, usize>,
+    read: &mut BTreeSet<usize>,
+) {
+    match stmt {
+        Statement::Assign { var_index, value } => {
+            assigned.entry(*var_index).or_insert(line);
+            collect_from_expr(value, read);
+        },
+        Statement::Input { var_index } | Statement::InputChar { var_index } => {
+            assigned.entry(*var_index).or_insert(line);
+        },
+        Statement::InputMany { var_index, count } => {
+            for var_index in *var_index..var_index + count {
+                assigned.entry(var_index).or_insert(line);
+            }
+        },
+        Statement::PrintNum(expr)
+        | Statement::PrintNumRadix { expr, .. }
+        | Statement::PrintChar(expr)
+        | Statement::GotoDynamic(expr)
+        | Statement::Return(expr)
+        | Statement::Eval(expr) => {
+            collect_from_expr(expr, read);
+        },
+        Statement::Conditional {
+            condition,
+            body,
+            else_body,
+        } => {
+            collect_from_expr(condition, read);
+            for s in body.iter().chain(else_body) {
+                collect_from_statement(s, line, assigned, read);
+            }
+        },
+        Statement::While { condition, body, .. } => {
+            collect_from_expr(condition, read);
+            for (s, span) in body {
+                collect_from_statement(s, span.line, assigned, read);
+            }
+        },
+        Statement::PrintNewline | Statement::PrintStr(_) | Statement::Goto { .. } => {},
+    }
+}
+
+/// Indices of the top-level statements that can never run: each follows a
+/// goto or return, and no goto lands on it. A goto to an empty line counts as
+/// landing on the next statement, and a computed goto may land on the first
+/// statement of any line. Targets are taken from every goto, reachable or not,
+/// so a statement only a dead goto jumps to is conservatively kept.
+pub(crate) fn unreachable_statements(program: &Program) -> BTreeSet<usize> {
+    let mut targets = BTreeSet::new();
+    let mut dynamic = false;
+    for (stmt, _) in &program.statements {
+        collect_goto_targets(stmt, &mut targets, &mut dynamic);
+    }
+
+    let mut unreachable = BTreeSet::new();
+    let mut falls_through = true;
+    let mut prev_line = 0;
+    for (idx, (stmt, line)) in program.statements.iter().enumerate() {
+        let line_start = *line != prev_line;
+        let jumped_to = line_start && (dynamic || targets.range(prev_line + 1..=*line).next().is_some());
+        let reachable = falls_through || jumped_to;
+        if !reachable {
+            unreachable.insert(idx);
+        }
+        falls_through = reachable
+            && !matches!(
+                stmt,
+                Statement::Goto { .. } | Statement::GotoDynamic(_) | Statement::Return(_)
+            );
+        prev_line = *line;
+    }
+    unreachable
+}
+
+fn collect_goto_targets(stmt: &Statement, targets: &mut BTreeSet<usize>, dynamic: &mut bool) {
+    match stmt {
+        Statement::Goto { target, .. } => {
+            targets.insert(*target);
+        },
+        Statement::GotoDynamic(_) => *dynamic = true,
+        Statement::Conditional { body, else_body, .. } => {
+            for s in body.iter().chain(else_body) {
+                collect_goto_targets(s, targets, dynamic);
+            }
+        },
+        _ => {},
+    }
+}
+
+// An empty body with an else part (`동탄어?:...`) is a deliberate "if nonzero"
+fn find_empty_conditionals(stmt: &Statement, line: usize, lines: &mut Vec<usize>) {
+    if let Statement::Conditional { body, else_body, .. } = stmt {
+        if body.is_empty() && else_body.is_empty() {
+            lines.push(line);
+        }
+        for s in body.iter().chain(else_body) {
+            find_empty_conditionals(s, line, lines);
+        }
+    }
+}
+
+fn collect_from_expr(expr: &Expr, read: &mut BTreeSet<usize>) {
+    match expr {
+        Expr::Number(_) | Expr::InputNum => {},
+        Expr::Var(index) => {
+            read.insert(*index);
+        },
+        Expr::Add(left, right)
+        | Expr::Sub(left, right)
+        | Expr::Mul(left, right)
+        | Expr::Mod(left, right)
+        | Expr::Eq(left, right)
+        | Expr::Lt(left, right)
+        | Expr::Gt(left, right) => {
+            collect_from_expr(left, read);
+            collect_from_expr(right, read);
+        },
+        Expr::Pow(base, _) | Expr::Neg(base) => collect_from_expr(base, read),
+    }
+}
@@ -0,0 +1,379 @@
+use crate::ast::{Expr, Program, Statement};
+use crate::codegen::CodeGenerator;
+use crate::error::CompileError;
+use crate::lower::lower_loops;
+
+// I/O goes through libc. The integer reader is the same algorithm as the other
+// backends: skip spaces/newlines, then accumulate decimal digits until the
+// first non-digit byte.
+const PRELUDE: &str = r#"declare i32 @getchar()
+declare i32 @putchar(i32)
+declare i32 @printf(i8*, ...)
+
+@.fmt_num = private unnamed_addr constant [6 x i8] c"%lld\0A\00"
+
+define internal i64 @um_read_int() {
+entry:
+  br label %skip
+
+skip:
+  %c = call i32 @getchar()
+  %is_space = icmp eq i32 %c, 32
+  %is_newline = icmp eq i32 %c, 10
+  %is_ws = or i1 %is_space, %is_newline
+  br i1 %is_ws, label %skip, label %digits
+
+digits:
+  %acc = phi i64 [ 0, %skip ], [ %next_acc, %digit ]
+  %cur = phi i32 [ %c, %skip ], [ %next_c, %digit ]
+  %offset = sub i32 %cur, 48
+  %is_digit = icmp ult i32 %offset, 10
+  br i1 %is_digit, label %digit, label %done
+
+digit:
+  %offset64 = zext i32 %offset to i64
+  %scaled = mul i64 %acc, 10
+  %next_acc = add i64 %scaled, %offset64
+  %next_c = call i32 @getchar()
+  br label %digits
+
+done:
+  ret i64 %acc
+}
+
+; Sign, then lowercase digits of the non-positive magnitude (which can hold
+; INT64_MIN), then a newline. The leading digits come from magnitude / radix,
+; whose place values always fit.
+define internal void @um_print_radix(i64 %value, i64 %radix) {
+entry:
+  %is_neg = icmp slt i64 %value, 0
+  br i1 %is_neg, label %sign, label %flip
+
+sign:
+  %minus = call i32 @putchar(i32 45)
+  br label %start
+
+flip:
+  %flipped = sub i64 0, %value
+  br label %start
+
+start:
+  %mag = phi i64 [ %value, %sign ], [ %flipped, %flip ]
+  %rest = sdiv i64 %mag, %radix
+  %limit = sub i64 0, %radix
+  %has_rest = icmp slt i64 %rest, 0
+  br i1 %has_rest, label %scale, label %last
+
+scale:
+  %pow = phi i64 [ 1, %start ], [ %next_pow, %scale_up ]
+  %lead = sdiv i64 %rest, %pow
+  %more = icmp sle i64 %lead, %limit
+  br i1 %more, label %scale_up, label %digits
+
+scale_up:
+  %next_pow = mul i64 %pow, %radix
+  br label %scale
+
+digits:
+  %place = phi i64 [ %pow, %scale ], [ %next_place, %digits ]
+  %quotient = sdiv i64 %rest, %place
+  %neg_digit = srem i64 %quotient, %radix
+  %is_letter = icmp slt i64 %neg_digit, -9
+  %letter_gap = select i1 %is_letter, i64 39, i64 0
+  %decimal = sub i64 48, %neg_digit
+  %byte = add i64 %decimal, %letter_gap
+  %byte32 = trunc i64 %byte to i32
+  %written = call i32 @putchar(i32 %byte32)
+  %next_place = sdiv i64 %place, %radix
+  %remaining = icmp sgt i64 %next_place, 0
+  br i1 %remaining, label %digits, label %last
+
+last:
+  %last_neg_digit = srem i64 %mag, %radix
+  %last_is_letter = icmp slt i64 %last_neg_digit, -9
+  %last_letter_gap = select i1 %last_is_letter, i64 39, i64 0
+  %last_decimal = sub i64 48, %last_neg_digit
+  %last_byte = add i64 %last_decimal, %last_letter_gap
+  %last_byte32 = trunc i64 %last_byte to i32
+  %last_written = call i32 @putchar(i32 %last_byte32)
+  %newline = call i32 @putchar(i32 10)
+  ret void
+}
+"#;
+
+/// Lowers a program to textual LLVM IR, for toolchains without Lamina.
+///
+/// Variables are `alloca` slots in `@main`, arithmetic wraps like the other
+/// backends, and I/O calls `getchar`/`putchar`/`printf`. Each statement gets
+/// its own block, so a goto to line N branches to the first statement on or
+/// after line N.
+pub fn generate_llvm_ir(program: &Program) -> Result<String, CompileError> {
+    let program = &*lower_loops(program);
+    let max_line = program.statements.iter().map(|(_, line)| *line).max().unwrap_or(1);
+    for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+        CodeGenerator::check_goto_targets(stmt, max_line, program.span(idx))?;
+    }
+
+    let mut generator = LlvmGenerator {
+        output: String::new(),
+        lines: program.statements.iter().map(|(_, line)| *line).collect(),
+        max_line,
+        temp_counter: 0,
+        block_counter: 0,
+    };
+    generator.generate(program);
+    Ok(generator.output)
+}
+
+struct LlvmGenerator {
+    output: String,
+    lines: Vec<usize>, // Source line of each top-level statement
+    max_line: usize,
+    temp_counter: usize,
+    block_counter: usize,
+}
+
+impl LlvmGenerator {
+    fn generate(&mut self, program: &Program) {
+        self.output.push_str(PRELUDE);
+        self.output.push_str("\ndefine i32 @main() {\nentry:\n");
+        for var_idx in CodeGenerator::collect_used_variables(program) {
+            self.line(&format!("%var_{} = alloca i64", var_idx));
+            self.line(&format!("store i64 0, i64* %var_{}", var_idx));
+        }
+
+        for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+            self.line(&format!("br label %s{}", idx));
+            self.label(&format!("s{}", idx));
+            self.generate_statement(stmt);
+        }
+
+        self.line("br label %exit");
+        self.label("exit");
+        self.line("ret i32 0");
+        self.output.push_str("}\n");
+    }
+
+    // Block a goto to `line` lands on; jumping past the last statement ends the program
+    fn target_block(&self, line: usize) -> String {
+        match self.lines.iter().position(|stmt_line| *stmt_line >= line) {
+            Some(idx) => format!("%s{}", idx),
+            None => "%exit".to_string(),
+        }
+    }
+
+    fn line(&mut self, code: &str) {
+        self.output.push_str("  ");
+        self.output.push_str(code);
+        self.output.push('\n');
+    }
+
+    fn label(&mut self, name: &str) {
+        self.output.push_str(&format!("\n{}:\n", name));
+    }
+
+    fn fresh_label(&mut self, prefix: &str) -> String {
+        let label = format!("{}_{}", prefix, self.block_counter);
+        self.block_counter += 1;
+        label
+    }
+
+    fn new_temp(&mut self) -> String {
+        let temp = format!("%t{}", self.temp_counter);
+        self.temp_counter += 1;
+        temp
+    }
+
+    // Terminators close the current block; anything after them (e.g. statements
+    // following a goto in a conditional body) goes into a fresh, unreachable one
+    fn terminate(&mut self, code: &str) {
+        self.line(code);
+        let after = self.fresh_label("after");
+        self.label(&after);
+    }
+
+    // Every statement leaves an open block for the next one to continue in
+    fn generate_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Assign { var_index, value } => {
+                let value = self.expr(value);
+                self.line(&format!("store i64 {}, i64* %var_{}", value, var_index));
+            },
+            Statement::Input { var_index } => {
+                let value = self.expr(&Expr::InputNum);
+                self.line(&format!("store i64 {}, i64* %var_{}", value, var_index));
+            },
+            Statement::InputMany { var_index, count } => {
+                for var_index in *var_index..var_index + count {
+                    self.generate_statement(&Statement::Input { var_index });
+                }
+            },
+            Statement::InputChar { var_index } => {
+                // getchar's EOF is -1, which sign-extends to -1
+                let byte = self.new_temp();
+                self.line(&format!("{} = call i32 @getchar()", byte));
+                let value = self.new_temp();
+                self.line(&format!("{} = sext i32 {} to i64", value, byte));
+                self.line(&format!("store i64 {}, i64* %var_{}", value, var_index));
+            },
+            Statement::PrintNum(expr) => {
+                let value = self.expr(expr);
+                let result = self.new_temp();
+                self.line(&format!(
+                    "{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([6 x i8], [6 x i8]* @.fmt_num, \
+                     i64 0, i64 0), i64 {})",
+                    result, value
+                ));
+            },
+            Statement::PrintNumRadix { expr, radix } => {
+                let value = self.expr(expr);
+                self.line(&format!("call void @um_print_radix(i64 {}, i64 {})", value, radix));
+            },
+            Statement::PrintChar(expr) => {
+                let value = self.expr(expr);
+                let byte = self.new_temp();
+                self.line(&format!("{} = trunc i64 {} to i32", byte, value));
+                let result = self.new_temp();
+                self.line(&format!("{} = call i32 @putchar(i32 {})", result, byte));
+            },
+            Statement::PrintNewline => {
+                let result = self.new_temp();
+                self.line(&format!("{} = call i32 @putchar(i32 10)", result));
+            },
+            Statement::PrintStr(text) => {
+                for byte in text.bytes() {
+                    let result = self.new_temp();
+                    self.line(&format!("{} = call i32 @putchar(i32 {})", result, byte));
+                }
+            },
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                // Body runs when the condition is zero
+                let value = self.expr(condition);
+                let is_zero = self.new_temp();
+                self.line(&format!("{} = icmp eq i64 {}, 0", is_zero, value));
+
+                let then_block = self.fresh_label("then");
+                let else_block = self.fresh_label("else");
+                let end_block = self.fresh_label("endif");
+                self.line(&format!("br i1 {}, label %{}, label %{}", is_zero, then_block, else_block));
+
+                self.label(&then_block);
+                for s in body {
+                    self.generate_statement(s);
+                }
+                self.line(&format!("br label %{}", end_block));
+
+                self.label(&else_block);
+                for s in else_body {
+                    self.generate_statement(s);
+                }
+                self.line(&format!("br label %{}", end_block));
+
+                self.label(&end_block);
+            },
+            Statement::While { .. } => unreachable!("loops are lowered before codegen"),
+            Statement::Goto { target, .. } => {
+                let block = self.target_block(*target);
+                self.terminate(&format!("br label {}", block));
+            },
+            Statement::GotoDynamic(expr) => {
+                // Unknown targets end the program like the Lamina backend
+                let value = self.expr(expr);
+                let cases: Vec<String> = (1..=self.max_line)
+                    .map(|line| format!("i64 {}, label {}", line, self.target_block(line)))
+                    .collect();
+                self.terminate(&format!("switch i64 {}, label %exit [ {} ]", value, cases.join(" ")));
+            },
+            Statement::Return(expr) => {
+                let value = self.expr(expr);
+                let status = self.new_temp();
+                self.line(&format!("{} = trunc i64 {} to i32", status, value));
+                self.terminate(&format!("ret i32 {}", status));
+            },
+            Statement::Eval(expr) => {
+                self.expr(expr);
+            },
+        }
+    }
+
+    // Returns an operand: a literal or the temp holding the value
+    fn expr(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Number(n) => n.to_string(),
+            Expr::Var(index) => {
+                let value = self.new_temp();
+                self.line(&format!("{} = load i64, i64* %var_{}", value, index));
+                value
+            },
+            Expr::InputNum => {
+                let value = self.new_temp();
+                self.line(&format!("{} = call i64 @um_read_int()", value));
+                value
+            },
+            Expr::Add(left, right) => self.binary("add", left, right),
+            Expr::Sub(left, right) => self.binary("sub", left, right),
+            Expr::Mul(left, right) => self.binary("mul", left, right),
+            Expr::Mod(left, right) => {
+                // srem overflows on MIN % -1, whose result is 0 anyway; x % 1 gives
+                // that 0 without the trap
+                let left = self.expr(left);
+                let right = self.expr(right);
+                let is_minus_one = self.new_temp();
+                self.line(&format!("{} = icmp eq i64 {}, -1", is_minus_one, right));
+                let divisor = self.new_temp();
+                self.line(&format!("{} = select i1 {}, i64 1, i64 {}", divisor, is_minus_one, right));
+                let result = self.new_temp();
+                self.line(&format!("{} = srem i64 {}, {}", result, left, divisor));
+                result
+            },
+            Expr::Pow(base, exp) => {
+                // Evaluate the base once, then multiply it in exp - 1 more times
+                if *exp == 0 {
+                    if base.reads_input() {
+                        self.expr(base);
+                    }
+                    return "1".to_string();
+                }
+                let base = self.expr(base);
+                let mut result = base.clone();
+                for _ in 1..*exp {
+                    let product = self.new_temp();
+                    self.line(&format!("{} = mul i64 {}, {}", product, result, base));
+                    result = product;
+                }
+                result
+            },
+            Expr::Neg(operand) => {
+                let operand = self.expr(operand);
+                let result = self.new_temp();
+                self.line(&format!("{} = sub i64 0, {}", result, operand));
+                result
+            },
+            Expr::Eq(left, right) => self.comparison("eq", left, right),
+            Expr::Lt(left, right) => self.comparison("slt", left, right),
+            Expr::Gt(left, right) => self.comparison("sgt", left, right),
+        }
+    }
+
+    fn binary(&mut self, op: &str, left: &Expr, right: &Expr) -> String {
+        let left = self.expr(left);
+        let right = self.expr(right);
+        let result = self.new_temp();
+        self.line(&format!("{} = {} i64 {}, {}", result, op, left, right));
+        result
+    }
+
+    fn comparison(&mut self, op: &str, left: &Expr, right: &Expr) -> String {
+        let left = self.expr(left);
+        let right = self.expr(right);
+        let flag = self.new_temp();
+        self.line(&format!("{} = icmp {} i64 {}, {}", flag, op, left, right));
+        let result = self.new_temp();
+        self.line(&format!("{} = zext i1 {} to i64", result, flag));
+        result
+    }
+}
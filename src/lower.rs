@@ -0,0 +1,82 @@
+use crate::ast::{Expr, Program, Span, Statement};
+use std::borrow::Cow;
+
+/// Rewrites every `동탄...??` loop into the conditionals and gotos the
+/// backends already understand, so none of them has to handle `While`.
+///
+/// A loop on line `L` whose body ends on line `E` becomes:
+///
+/// - on `L`, a conditional that leaves the loop when the expression is
+///   nonzero, by jumping to line `E + 1` (or returning 0 if nothing follows);
+/// - the body statements at the top level, on their own source lines;
+/// - on `E`, a goto back to `L`.
+///
+/// Loops start and end their lines, so those gotos land exactly on the loop
+/// head and on what follows the loop. Lines, and so every other goto target,
+/// stay the same. Programs without loops are returned as they are.
+pub fn lower_loops(program: &Program) -> Cow<'_, Program> {
+    if !program.statements.iter().any(|(stmt, _)| matches!(stmt, Statement::While { .. })) {
+        return Cow::Borrowed(program);
+    }
+
+    let last_line = program
+        .statements
+        .iter()
+        .map(|(stmt, line)| match stmt {
+            Statement::While { end_line, .. } => *end_line,
+            _ => *line,
+        })
+        .max()
+        .unwrap_or(1);
+
+    let mut lowered = Vec::new();
+    for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+        lower_statement(stmt, program.span(idx), last_line, &mut lowered);
+    }
+    let (statements, spans) = lowered.into_iter().map(|(stmt, span)| ((stmt, span.line), span)).unzip();
+    Cow::Owned(Program {
+        statements,
+        spans,
+        locals: program.locals.clone(),
+        missing_end_marker: program.missing_end_marker,
+    })
+}
+
+fn lower_statement(stmt: &Statement, span: Span, last_line: usize, out: &mut Vec<(Statement, Span)>) {
+    let Statement::While {
+        condition,
+        body,
+        end_line,
+    } = stmt
+    else {
+        out.push((stmt.clone(), span));
+        return;
+    };
+
+    let exit = if *end_line < last_line {
+        Statement::Goto {
+            target: end_line + 1,
+            line: span.line,
+        }
+    } else {
+        Statement::Return(Expr::Number(0))
+    };
+    out.push((
+        Statement::Conditional {
+            condition: condition.clone(),
+            body: Vec::new(),
+            else_body: vec![exit],
+        },
+        span,
+    ));
+    for (stmt, span) in body {
+        lower_statement(stmt, *span, last_line, out);
+    }
+    out.push((
+        Statement::Goto {
+            target: span.line,
+            line: *end_line,
+        },
+        span.at_line(*end_line),
+    ));
+}
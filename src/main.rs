@@ -1,9 +1,43 @@
-use clap::Parser;
-use lamina::{compile_lamina_ir_to_assembly, detect_host_architecture};
+use clap::{Parser, ValueEnum};
 use std::fs;
-use std::path::Path;
-use std::process::{self, Command};
-use umjunsik::compile_umjunsik;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use umjunsik::ast::Program;
+use umjunsik::codegen::{CodeGenerator, CodeGeneratorBuilder, OptLevel};
+use umjunsik::lexer::{expand_ascii_aliases, normalize_line_endings};
+use umjunsik::{
+    C_COMPILER_CANDIDATES, CompileError, InterpretOptions, Target, ast_to_dot, compile_umjunsik_with_options,
+    format_source, generate_c, generate_llvm_ir, generate_wat, host_target, ir_to_assembly_for, lint, parse,
+    parse_multi, parse_multi_strict, parse_strict, render_diagnostic, select_c_compiler, tokenize, validate,
+};
+
+/// Compilation stage to print
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Emit {
+    /// Token stream with line/column positions
+    Tokens,
+    /// Parsed program
+    Ast,
+    /// Parsed program as a Graphviz DOT graph
+    Dot,
+    /// Lamina IR
+    Ir,
+}
+
+/// Code generator to compile with
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Backend {
+    /// Lamina IR, assembled and linked natively by --run
+    Lamina,
+    /// WebAssembly text importing env.readbyte/writebyte/print
+    Wat,
+    /// Standalone C source
+    C,
+    /// Textual LLVM IR, compiled with llc and linked by --run
+    Llvm,
+}
 
 /// Umjunsik Language Compiler targeting Lamina IR
 #[derive(Parser)]
@@ -12,18 +46,104 @@ use umjunsik::compile_umjunsik;
 #[command(version)]
 #[command(about = "Compiles Umjunsik language (.umm files) to Lamina IR", long_about = None)]
 struct Cli {
-    /// Input Umjunsik source file (.umm)
-    #[arg(value_name = "FILE")]
-    input: String,
+    /// Input Umjunsik source files (.umm); several are compiled as one program, in order
+    #[arg(value_name = "FILE", required = true)]
+    input: Vec<String>,
 
     /// Compile and execute instead of showing IR
     #[arg(short, long)]
     run: bool,
 
-    /// Save Lamina IR to file
+    /// Output stage to print
+    #[arg(long, value_enum, default_value = "ir", value_name = "STAGE")]
+    emit: Emit,
+
+    /// Code generator to use
+    #[arg(long, value_enum, default_value = "lamina", value_name = "BACKEND")]
+    backend: Backend,
+
+    /// Save the generated code to file
     #[arg(short, long, value_name = "FILE")]
     output: Option<String>,
 
+    /// Lamina target for --run, e.g. aarch64_linux (defaults to the host)
+    #[arg(short, long, value_name = "ARCH")]
+    target: Option<Target>,
+
+    /// Directory for intermediate assembly and executables (defaults to the system temp dir)
+    #[arg(long, value_name = "DIR")]
+    temp_dir: Option<PathBuf>,
+
+    /// Keep intermediate files instead of deleting them after --run
+    #[arg(long)]
+    keep_temps: bool,
+
+    /// C compiler that assembles and links for --run (defaults to $CC, then clang, cc or gcc)
+    #[arg(long, value_name = "PATH")]
+    cc: Option<String>,
+
+    /// Exit with an error on i64 overflow instead of wrapping (compiled code and --interpret)
+    #[arg(long)]
+    checked: bool,
+
+    /// Lamina IR optimization level: 0 (none; the default), 1 (constant folding and reuse,
+    /// fewer labels) or 2 (also jump threading, SSA variables and shared stack slots)
+    #[arg(
+        short = 'O',
+        long = "optimize",
+        value_name = "LEVEL",
+        value_parser = clap::value_parser!(u8).range(0..=2)
+    )]
+    optimize: Option<u8>,
+
+    /// Print a unified diff from the -O0 IR to the IR at the -O level (2 if -O is not given) instead of the IR
+    #[arg(long, conflicts_with_all = ["run", "interpret", "stats", "verify_ir", "output"])]
+    opt_diff: bool,
+
+    /// Check that the generated IR parses as Lamina IR before printing or running it
+    #[arg(long)]
+    verify_ir: bool,
+
+    /// Print source/IR sizes and per-stage compile times to stderr
+    #[arg(long)]
+    stats: bool,
+
+    /// Print numbers digit by digit with writebyte instead of Lamina's print
+    #[arg(long)]
+    portable_print: bool,
+
+    /// Run a 동탄 body when its expression is nonzero instead of zero
+    #[arg(long)]
+    truthy_conditionals: bool,
+
+    /// Exit with an error after running N statements (stops runaway goto loops; compiled code and --interpret)
+    #[arg(long, value_name = "N")]
+    max_lines: Option<u64>,
+
+    /// Feed this string to the program's stdin instead of the terminal
+    #[arg(long, value_name = "STRING", conflicts_with = "input_file")]
+    stdin_input: Option<String>,
+
+    /// Feed this file to the program's stdin instead of the terminal
+    #[arg(long, value_name = "FILE")]
+    input_file: Option<PathBuf>,
+
+    /// Treat a source that ends without the 이 사람이름이냐ㅋㅋ end marker as an error, not a warning
+    #[arg(long)]
+    strict: bool,
+
+    /// Accept ASCII aliases (start, end, print, if, goto, return, a, m, k) for the Hangul keywords
+    #[arg(long)]
+    ascii_aliases: bool,
+
+    /// Print the source in canonical form (or write it to --output) instead of compiling
+    #[arg(long, conflicts_with_all = ["run", "interpret", "stats"])]
+    fmt: bool,
+
+    /// Execute with the built-in interpreter (no Lamina or C compiler needed)
+    #[arg(short, long)]
+    interpret: bool,
+
     /// Suppress output messages
     #[arg(short, long)]
     quiet: bool,
@@ -32,21 +152,138 @@ struct Cli {
 fn main() {
     let cli = Cli::parse();
 
-    // Read the source file
-    let source = fs::read_to_string(&cli.input).unwrap_or_else(|err| {
-        eprintln!("[umjunsik] Error reading file '{}': {}", cli.input, err);
+    // Compiler traces go to stderr, filtered by UMJUNSIK_LOG (e.g. UMJUNSIK_LOG=umjunsik=trace)
+    #[cfg(feature = "trace")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_env("UMJUNSIK_LOG"))
+        .with_writer(io::stderr)
+        .init();
+
+    // Read the source files
+    let sources: Vec<String> = cli
+        .input
+        .iter()
+        .map(|path| {
+            let source = fs::read_to_string(path).unwrap_or_else(|err| {
+                eprintln!("[umjunsik] Error reading file '{}': {}", path, err);
+                process::exit(1);
+            });
+            // Expanded up front so every stage (and its diagnostics) sees the Hangul form
+            if cli.ascii_aliases { expand_ascii_aliases(&source) } else { source }
+        })
+        .collect();
+    let multi = sources.len() > 1;
+    // Errors in a merged program carry merged line numbers, which point into the files joined end to end
+    let joined;
+    let source = if multi {
+        joined = join_sources(&sources);
+        &joined
+    } else {
+        &sources[0]
+    };
+
+    if cli.fmt {
+        if multi {
+            eprintln!("[umjunsik] --fmt formats one file at a time");
+            process::exit(1);
+        }
+        format_file(source, &cli);
+        return;
+    }
+
+    // Tokens and stats are per file; everything else works on the merged program
+    if multi && (cli.emit == Emit::Tokens || cli.stats) {
+        eprintln!("[umjunsik] --emit tokens and --stats take a single input file");
         process::exit(1);
-    });
+    }
 
-    // Compile to Lamina IR
-    let lamina_ir = match compile_umjunsik(&source) {
-        Ok(ir) => ir,
-        Err(err) => {
-            eprintln!("[umjunsik] Compilation error: {}", err);
+    // Otherwise a missing end marker is only reported with the other warnings
+    if cli.strict {
+        parse_sources(&sources, true).unwrap_or_else(|err| report_compile_error(source, &err));
+    }
+
+    // Intermediate stages have no IR to execute, or to compare
+    if cli.emit != Emit::Ir {
+        if cli.opt_diff {
+            eprintln!("[umjunsik] --opt-diff compares Lamina IR and cannot be combined with --emit tokens/ast/dot");
             process::exit(1);
-        },
+        }
+        if cli.run {
+            eprintln!("[umjunsik] --emit tokens/ast/dot cannot be combined with --run (there is no IR to execute)");
+            process::exit(1);
+        }
+        emit_stage(source, &sources, cli.emit, cli.quiet);
+        return;
+    }
+
+    let program = parse_sources(&sources, false).unwrap_or_else(|err| report_compile_error(source, &err));
+    // Every backend, the interpreter included, rejects what validate rejects
+    if let Err(errors) = validate(&program) {
+        report_compile_error(source, &errors[0]);
+    }
+    if !cli.quiet {
+        for warning in lint(&program) {
+            eprintln!("[umjunsik] Warning: {}", warning);
+        }
+    }
+
+    // Interpret directly, bypassing Lamina entirely
+    if cli.interpret {
+        run_with_interpreter(&program, &cli);
+        return;
+    }
+
+    // Other backends produce source text; only LLVM IR can be assembled and run
+    if cli.backend != Backend::Lamina {
+        if cli.checked
+            || cli.stats
+            || cli.verify_ir
+            || cli.portable_print
+            || cli.truthy_conditionals
+            || cli.max_lines.is_some()
+            || cli.opt_diff
+        {
+            eprintln!(
+                "[umjunsik] --checked, --stats, --verify-ir, --portable-print, --truthy-conditionals, \
+                 --max-lines and --opt-diff require the lamina backend"
+            );
+            process::exit(1);
+        }
+        if cli.run && cli.backend != Backend::Llvm {
+            eprintln!("[umjunsik] --run requires the lamina or llvm backend");
+            process::exit(1);
+        }
+        emit_backend(source, &program, &cli);
+        return;
+    }
+
+    if cli.opt_diff {
+        // Without -O, show everything the optimizer can change
+        let builder = match cli.optimize {
+            Some(_) => codegen_builder(&cli),
+            None => codegen_builder(&cli).opt_level(OptLevel::O2),
+        };
+        let diff = builder.opt_diff(&program).unwrap_or_else(|err| report_compile_error(source, &err));
+        print!("{}", diff);
+        return;
+    }
+
+    // Compile to Lamina IR
+    let lamina_ir = if cli.stats {
+        // Lexes and parses again, so the stats time every stage
+        let (lamina_ir, stats) = compile_umjunsik_with_options(source, codegen_builder(&cli))
+            .unwrap_or_else(|err| report_compile_error(source, &err));
+        eprintln!("[umjunsik] Compile stats:\n{}", stats);
+        lamina_ir
+    } else {
+        let mut codegen = codegen_builder(&cli).build();
+        codegen.generate(&program).unwrap_or_else(|err| report_compile_error(source, &err))
     };
 
+    if cli.verify_ir {
+        verify_ir(&lamina_ir, cli.quiet);
+    }
+
     // Save to file if --output is specified
     if let Some(ref output_file) = cli.output {
         fs::write(output_file, &lamina_ir).unwrap_or_else(|err| {
@@ -61,7 +298,7 @@ fn main() {
     // Execute if --run flag is set, otherwise show IR
     if cli.run {
         // Compile and execute
-        run_with_lamina(&lamina_ir, &cli.input, cli.quiet);
+        run_with_lamina(&lamina_ir, &cli);
     } else {
         // Default: show IR
         if !cli.quiet {
@@ -71,64 +308,337 @@ fn main() {
     }
 }
 
-fn run_with_lamina(lamina_ir: &str, source_file: &str, quiet: bool) {
-    // Detect host architecture
-    let target = detect_host_architecture();
+// Codegen options as given on the command line
+fn codegen_builder(cli: &Cli) -> CodeGeneratorBuilder {
+    CodeGenerator::builder()
+        .checked(cli.checked)
+        .portable_print(cli.portable_print)
+        .truthy_conditionals(cli.truthy_conditionals)
+        .max_lines(cli.max_lines)
+        .opt_level(match cli.optimize {
+            Some(1) => OptLevel::O1,
+            Some(2) => OptLevel::O2,
+            _ => OptLevel::O0,
+        })
+}
+
+// The program in `sources`, merged into one when there are several files
+fn parse_sources(sources: &[String], strict: bool) -> Result<Program, CompileError> {
+    let files: Vec<&str> = sources.iter().map(String::as_str).collect();
+    match (files.as_slice(), strict) {
+        ([source], false) => parse(source),
+        ([source], true) => parse_strict(source),
+        (files, false) => parse_multi(files),
+        (files, true) => parse_multi_strict(files),
+    }
+}
+
+fn emit_stage(source: &str, sources: &[String], emit: Emit, quiet: bool) {
+    match emit {
+        Emit::Tokens => {
+            let tokens = tokenize(source).unwrap_or_else(|err| report_compile_error(source, &err));
+            if !quiet {
+                println!("=== Tokens ===");
+            }
+            for token in tokens {
+                println!("{}:{} {:?}", token.line, token.col, token.token);
+            }
+        },
+        Emit::Ast => {
+            let program = parse_sources(sources, false).unwrap_or_else(|err| report_compile_error(source, &err));
+            if !quiet {
+                println!("=== AST ===");
+            }
+            print!("{}", program);
+        },
+        Emit::Dot => {
+            let program = parse_sources(sources, false).unwrap_or_else(|err| report_compile_error(source, &err));
+            print!("{}", ast_to_dot(&program));
+        },
+        Emit::Ir => unreachable!("IR is emitted by the main compile path"),
+    }
+}
+
+fn format_file(source: &str, cli: &Cli) {
+    let formatted = format_source(source).unwrap_or_else(|err| report_compile_error(source, &err));
+    match cli.output {
+        Some(ref output_file) => {
+            fs::write(output_file, &formatted).unwrap_or_else(|err| {
+                eprintln!("[umjunsik] Error writing to file '{}': {}", output_file, err);
+                process::exit(1);
+            });
+            if !cli.quiet {
+                println!("[umjunsik] Formatted source written to: {}", output_file);
+            }
+        },
+        None => print!("{}", formatted),
+    }
+}
+
+fn emit_backend(source: &str, program: &Program, cli: &Cli) {
+    let (name, code) = match cli.backend {
+        Backend::Wat => ("WAT", generate_wat(program)),
+        Backend::C => ("C", generate_c(program)),
+        Backend::Llvm => ("LLVM IR", generate_llvm_ir(program)),
+        Backend::Lamina => unreachable!("Lamina IR is emitted by the main compile path"),
+    };
+    let code = code.unwrap_or_else(|err| report_compile_error(source, &err));
+
+    if let Some(ref output_file) = cli.output {
+        fs::write(output_file, &code).unwrap_or_else(|err| {
+            eprintln!("[umjunsik] Error writing to file '{}': {}", output_file, err);
+            process::exit(1);
+        });
+        if !cli.quiet {
+            println!("[umjunsik] {} written to: {}", name, output_file);
+        }
+    }
+
+    if cli.run {
+        run_with_llvm(&code, cli);
+    } else if cli.output.is_none() {
+        if !cli.quiet {
+            println!("=== Generated {} ===", name);
+        }
+        print!("{}", code);
+    }
+}
+
+// The files joined end to end, which is what merged line numbers point into
+fn join_sources(sources: &[String]) -> String {
+    sources
+        .iter()
+        .map(|source| {
+            let mut source = normalize_line_endings(source);
+            if !source.ends_with('\n') {
+                source.push('\n');
+            }
+            source
+        })
+        .collect()
+}
+
+// Run the IR through Lamina's parser without generating any assembly
+fn verify_ir(lamina_ir: &str, quiet: bool) {
+    if let Err(err) = lamina::parser::parse_module(lamina_ir) {
+        eprintln!("[umjunsik] IR verification failed: {}", err);
+        // Lamina reports a byte offset; show the IR line it falls on
+        let message = err.to_string();
+        let offset = message
+            .rsplit_once("at position ")
+            .and_then(|(_, pos)| pos.trim().parse::<usize>().ok())
+            .and_then(|pos| lamina_ir.get(..pos));
+        if let Some(before) = offset {
+            let line = before.matches('\n').count() + 1;
+            let text = lamina_ir.lines().nth(line - 1).unwrap_or("");
+            eprintln!("  IR line {} | {}", line, text.trim());
+        }
+        process::exit(1);
+    }
+    if !quiet {
+        println!("[umjunsik] IR verified");
+    }
+}
+
+fn report_compile_error(source: &str, err: &CompileError) -> ! {
+    eprintln!("[umjunsik] Compilation error: {}", err);
+    if let Some((line, col)) = err.location() {
+        eprintln!("{}", render_diagnostic(source, line, col));
+    }
+    process::exit(1);
+}
+
+// Whether `program` names an executable in one of the PATH directories
+fn on_path(program: &str) -> bool {
+    let file_name = format!("{}{}", program, std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(&file_name).is_file()))
+}
+
+fn open_input_file(path: &Path) -> fs::File {
+    fs::File::open(path).unwrap_or_else(|err| {
+        eprintln!("[umjunsik] Error reading input file '{}': {}", path.display(), err);
+        process::exit(1);
+    })
+}
+
+fn run_with_interpreter(program: &Program, cli: &Cli) {
+    let stdin = io::stdin();
+    let mut input: Box<dyn Read> = match (&cli.stdin_input, &cli.input_file) {
+        (Some(text), _) => Box::new(io::Cursor::new(text.clone().into_bytes())),
+        (None, Some(path)) => Box::new(open_input_file(path)),
+        (None, None) => Box::new(stdin.lock()),
+    };
+    let stdout = io::stdout();
+    let options = InterpretOptions::default()
+        .checked(cli.checked)
+        .truthy_conditionals(cli.truthy_conditionals)
+        .max_lines(cli.max_lines);
+    let exit_code = options.interpret(program, &mut input, &mut stdout.lock()).unwrap_or_else(|err| {
+        eprintln!("[umjunsik] Runtime error: {}", err);
+        process::exit(1);
+    });
+
+    // Like a native main returning i64, the OS keeps only the low 8 bits
+    if exit_code != 0 {
+        process::exit(exit_code as i32);
+    }
+}
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Unique temp path stem so concurrent compilations of the same file don't collide
+fn temp_stem(source_file: &str, temp_dir: Option<&Path>) -> PathBuf {
+    let dir = temp_dir.map(Path::to_path_buf).unwrap_or_else(std::env::temp_dir);
+    let stem = Path::new(source_file)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("umjunsik");
+    let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!("{}-{}-{}", stem, process::id(), counter))
+}
+
+fn run_with_lamina(lamina_ir: &str, cli: &Cli) {
+    let quiet = cli.quiet;
+
+    // Use the requested target (already checked by Target::from_str), or the host's
+    let target = match cli.target {
+        Some(target) => target,
+        None => host_target().unwrap_or_else(|err| {
+            eprintln!("[umjunsik] Cannot compile for this machine: {}; pass --target", err);
+            process::exit(1);
+        }),
+    };
 
     if !quiet {
         println!("[umjunsik] Compiling with lamina for {}...", target);
     }
 
-    // Compile IR to assembly using lamina library
-    let mut assembly = Vec::new();
-    if let Err(err) = compile_lamina_ir_to_assembly(lamina_ir, &mut assembly) {
-        eprintln!("[umjunsik] Lamina compilation error: {}", err);
+    let assembly = ir_to_assembly_for(lamina_ir, target).unwrap_or_else(|err| {
+        eprintln!("[umjunsik] {}", err);
         process::exit(1);
+    });
+
+    link_and_run(&assembly, cli);
+}
+
+fn run_with_llvm(llvm_ir: &str, cli: &Cli) {
+    if cli.target.is_some() {
+        eprintln!("[umjunsik] --target only applies to the lamina backend; llc compiles for the host");
+        process::exit(1);
+    }
+    if !cli.quiet {
+        println!("[umjunsik] Compiling with llc...");
     }
 
+    // llc reads the IR from stdin and writes assembly to stdout; PIC so it links as a PIE
+    let mut llc = Command::new("llc")
+        .args(["-relocation-model=pic", "-o", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|err| {
+            eprintln!("[umjunsik] Error running llc: {}", err);
+            eprintln!("[umjunsik] Make sure LLVM is installed");
+            process::exit(1);
+        });
+    if let Some(mut stdin) = llc.stdin.take() {
+        let _ = stdin.write_all(llvm_ir.as_bytes());
+    }
+    let output = llc.wait_with_output().unwrap_or_else(|err| {
+        eprintln!("[umjunsik] Error running llc: {}", err);
+        process::exit(1);
+    });
+    if !output.status.success() {
+        eprintln!("[umjunsik] llc compilation failed");
+        process::exit(1);
+    }
+
+    link_and_run(&output.stdout, cli);
+}
+
+// Assemble and link `assembly` with a C compiler, run it, and forward its exit status
+fn link_and_run(assembly: &[u8], cli: &Cli) {
+    let quiet = cli.quiet;
+    // Open the input up front so a bad path fails before any temp files exist
+    let input_file = cli.input_file.as_deref().map(open_input_file);
+    let env_cc = std::env::var("CC").ok();
+    let cc = select_c_compiler(cli.cc.as_deref(), env_cc.as_deref(), on_path).unwrap_or_else(|| {
+        eprintln!(
+            "[umjunsik] No C compiler found to link with (tried {})",
+            C_COMPILER_CANDIDATES.join(", ")
+        );
+        eprintln!("[umjunsik] Install one, set CC, or pass --cc <PATH>");
+        process::exit(1);
+    });
+
     // Create temporary files
-    let temp_asm = format!(
-        "/tmp/{}.s",
-        Path::new(source_file).file_stem().unwrap().to_str().unwrap()
-    );
-    let temp_exe = format!("/tmp/{}", Path::new(source_file).file_stem().unwrap().to_str().unwrap());
+    let temp_exe = temp_stem(&cli.input[0], cli.temp_dir.as_deref());
+    let mut temp_asm = temp_exe.clone().into_os_string();
+    temp_asm.push(".s");
+    let temp_asm = PathBuf::from(temp_asm);
+    let cleanup = || {
+        if !cli.keep_temps {
+            let _ = fs::remove_file(&temp_asm);
+            let _ = fs::remove_file(&temp_exe);
+        }
+    };
 
     // Write assembly to temp file
-    fs::write(&temp_asm, &assembly).unwrap_or_else(|err| {
+    fs::write(&temp_asm, assembly).unwrap_or_else(|err| {
         eprintln!("[umjunsik] Error writing assembly file: {}", err);
         process::exit(1);
     });
 
-    // Assemble and link with clang
+    // Assemble and link
     if !quiet {
-        println!("[umjunsik] Assembling and linking...");
+        println!("[umjunsik] Assembling and linking with {}...", cc);
     }
-    let link_status = Command::new("clang")
+    let link_status = Command::new(&cc)
         .arg(&temp_asm)
         .arg("-o")
         .arg(&temp_exe)
         .status()
         .unwrap_or_else(|err| {
-            eprintln!("[umjunsik] Error running clang: {}", err);
-            eprintln!("[umjunsik] Make sure clang is installed");
+            eprintln!("[umjunsik] Error running {}: {}", cc, err);
+            eprintln!("[umjunsik] Make sure it is installed, or pick another compiler with --cc");
+            cleanup();
             process::exit(1);
         });
 
     if !link_status.success() {
         eprintln!("[umjunsik] Linking failed");
+        cleanup();
         process::exit(1);
     }
 
-    // Execute
-    let run_status = Command::new(&temp_exe).status().unwrap_or_else(|err| {
+    // Execute, with stdin inherited from the terminal unless input was supplied
+    let mut command = Command::new(&temp_exe);
+    if cli.stdin_input.is_some() {
+        command.stdin(Stdio::piped());
+    } else if let Some(file) = input_file {
+        command.stdin(file);
+    }
+    let mut child = command.spawn().unwrap_or_else(|err| {
+        eprintln!("[umjunsik] Error executing program: {}", err);
+        process::exit(1);
+    });
+    if let (Some(text), Some(mut stdin)) = (&cli.stdin_input, child.stdin.take()) {
+        // The program may exit without reading everything; that's not an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let run_status = child.wait().unwrap_or_else(|err| {
         eprintln!("[umjunsik] Error executing program: {}", err);
         process::exit(1);
     });
 
     // Clean up
-    let _ = fs::remove_file(&temp_asm);
-    let _ = fs::remove_file(&temp_exe);
+    cleanup();
+    if cli.keep_temps && !quiet {
+        println!("[umjunsik] Kept {} and {}", temp_asm.display(), temp_exe.display());
+    }
 
+    // Forward the program's exit status (the 화이팅! value, truncated to 8 bits)
     if !run_status.success() {
         process::exit(run_status.code().unwrap_or(1));
     }
@@ -1,14 +1,68 @@
-use crate::ast::{Expr, Program, Statement};
+use crate::ast::{Expr, Program, Span, Statement};
+use crate::error::CompileError;
 use crate::token::{Token, TokenWithPos};
+use std::collections::{BTreeMap, HashMap};
+
+/// Highest variable index a program may use unless configured otherwise.
+pub const DEFAULT_MAX_VAR_INDEX: usize = 10000;
+
+// A constant expression whose value doesn't fit in i64
+struct ConstOverflow;
 
 pub struct Parser {
     tokens: Vec<TokenWithPos>,
     position: usize,
+    max_var_index: usize,
+    in_print: bool, // Spaces right before a closing ! or ㅋ don't multiply
+    strict: bool,   // A missing end marker is an error rather than a warning
+    // One scope per enclosing 동탄 body part, mapping the source index of each
+    // block-local variable declared in it to the fresh index it was given
+    scopes: Vec<HashMap<usize, usize>>,
+    locals: BTreeMap<usize, usize>, // Every fresh index handed out -> its source index
 }
 
 impl Parser {
     pub fn new(tokens: Vec<TokenWithPos>) -> Self {
-        Parser { tokens, position: 0 }
+        Parser {
+            tokens,
+            position: 0,
+            max_var_index: DEFAULT_MAX_VAR_INDEX,
+            in_print: false,
+            strict: false,
+            scopes: Vec::new(),
+            locals: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the highest variable index a program may assign or read. Every
+    /// variable gets its own stack slot, so this bounds what codegen allocates.
+    pub fn with_max_var_index(mut self, max_var_index: usize) -> Self {
+        self.max_var_index = max_var_index;
+        self
+    }
+
+    /// Makes reaching the end of the source without `이 사람이름이냐ㅋㅋ` a parse
+    /// error. Otherwise the program is accepted and flagged with
+    /// `missing_end_marker`, which `lint` reports as a warning.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn check_var_index(&self, index: usize) -> Result<usize, CompileError> {
+        if index > self.max_var_index {
+            return Err(self.error(format!(
+                "Variable index {} exceeds the limit of {}",
+                index, self.max_var_index
+            )));
+        }
+        Ok(index)
+    }
+
+    // The variable a source index refers to here: the innermost block-local
+    // declaration of it, or else the function-wide variable
+    fn resolve_var(&self, index: usize) -> usize {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&index)).copied().unwrap_or(index)
     }
 
     fn current_token(&self) -> &Token {
@@ -25,26 +79,68 @@ impl Parser {
         }
     }
 
-    fn expect(&mut self, expected: Token) -> Result<(), String> {
+    fn error(&self, message: impl Into<String>) -> CompileError {
+        let (line, col) = self
+            .tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|t| (t.line, t.col))
+            .unwrap_or((1, 1));
+        CompileError::Parse {
+            position: self.position,
+            line,
+            col,
+            message: message.into(),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), CompileError> {
         if self.current_token() == &expected {
             self.advance();
             Ok(())
         } else {
-            Err(format!(
+            Err(self.error(format!(
                 "Expected {:?}, found {:?} at position {}",
                 expected,
                 self.current_token(),
                 self.position
-            ))
+            )))
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Program, CompileError> {
+        let (program, mut errors) = self.parse_recovering();
+        match program {
+            Some(program) if errors.is_empty() => Ok(program),
+            _ => Err(errors.remove(0)),
         }
     }
 
-    pub fn parse(&mut self) -> Result<Program, String> {
+    /// Parses the whole program, collecting every statement error instead of
+    /// stopping at the first.
+    ///
+    /// After a broken statement the parser skips to the next newline or `~` and
+    /// carries on. The returned program holds the statements that did parse; it
+    /// is `None` only when the `어떻게` header itself is missing.
+    pub fn parse_recovering(&mut self) -> (Option<Program>, Vec<CompileError>) {
+        trace_span!("parse");
         // Expect program start: 어떻게
-        self.expect(Token::Eotteohke)?;
+        if !matches!(self.current_token(), Token::Eotteohke) {
+            let has_end = self.tokens.iter().any(|t| t.token == Token::IEotteonSaram);
+            let message = if has_end {
+                "program must begin with 어떻게"
+            } else {
+                "program must begin with 어떻게 and end with 이 사람이름이냐ㅋㅋ"
+            };
+            return (None, vec![self.error(message)]);
+        }
+        self.advance();
         self.skip_newlines();
 
         let mut statements = Vec::new();
+        let mut spans = Vec::new();
+        let mut errors = Vec::new();
+        let mut missing_end_marker = None;
 
         // Parse statements until we hit program end
         loop {
@@ -55,24 +151,99 @@ impl Parser {
                     self.advance();
                     break;
                 },
-                Token::EOF => break,
+                Token::EOF => {
+                    if self.strict {
+                        errors.push(self.error("program missing 이 사람이름이냐ㅋㅋ end marker"));
+                    }
+                    // The last line with a token, not the empty one after a final newline
+                    let last = self.tokens.iter().rfind(|t| !matches!(t.token, Token::Newline | Token::EOF));
+                    missing_end_marker = Some(last.map_or(1, |t| t.line));
+                    break;
+                },
                 Token::Tilde => {
                     self.advance(); // skip tilde (line separator for one-line code)
                 },
                 _ => {
-                    // Get the line number before parsing the statement
-                    let line_num = if self.position < self.tokens.len() {
-                        self.tokens[self.position].line
-                    } else {
-                        1
-                    };
-                    let stmt = self.parse_statement()?;
-                    statements.push((stmt, line_num));
+                    // Get the position before parsing the statement
+                    let span = self.current_span();
+                    match self.parse_statement() {
+                        Ok(stmt) => {
+                            statements.push((stmt, span.line));
+                            spans.push(span);
+                        },
+                        Err(err) => {
+                            errors.push(err);
+                            self.skip_to_statement_end();
+                        },
+                    }
                 },
             }
         }
 
-        Ok(Program { statements })
+        let locals = std::mem::take(&mut self.locals);
+        (
+            Some(Program {
+                statements,
+                spans,
+                locals,
+                missing_end_marker,
+            }),
+            errors,
+        )
+    }
+
+    fn current_span(&self) -> Span {
+        match self.tokens.get(self.position) {
+            Some(token) => Span {
+                line: token.line,
+                col: token.col,
+            },
+            None => Span { line: 1, col: 1 },
+        }
+    }
+
+    // Recovery point after an error: the next newline, `~`, or end of program
+    fn skip_to_statement_end(&mut self) {
+        while !matches!(
+            self.current_token(),
+            Token::Newline | Token::Tilde | Token::EOF | Token::IEotteonSaram
+        ) {
+            self.advance();
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.current_token(), Token::Space) {
+            self.advance();
+        }
+    }
+
+    // Whether the spaces at the current position run straight into a print's ! or ㅋ
+    fn spaces_before_print_end(&self) -> bool {
+        let next = self.tokens[self.position..]
+            .iter()
+            .find(|t| !matches!(t.token, Token::Space))
+            .map(|t| &t.token);
+        matches!(next, Some(Token::Exclamation | Token::Kek))
+    }
+
+    // Whether the first token after the current run of spaces can start an operand
+    fn operand_after_spaces(&self) -> bool {
+        let Some(offset) = self.tokens[self.position..].iter().position(|t| !matches!(t.token, Token::Space)) else {
+            return false;
+        };
+        let next = self.position + offset;
+        matches!(self.tokens[next].token, Token::Dot(_) | Token::Comma(_)) || self.starts_unary(next)
+    }
+
+    // Whether the token at `idx` starts what parse_unary reads: a variable, @
+    // literal, group, negation or 식? input
+    fn starts_unary(&self, idx: usize) -> bool {
+        match self.tokens.get(idx).map(|t| &t.token) {
+            Some(Token::Eo(_) | Token::Number(_) | Token::LParen | Token::Minus) => true,
+            Some(Token::Sik) => matches!(self.tokens.get(idx + 1).map(|t| &t.token), Some(Token::Question)),
+            _ => false,
+        }
     }
 
     fn skip_newlines(&mut self) {
@@ -81,91 +252,183 @@ impl Parser {
         }
     }
 
-    fn parse_statement(&mut self) -> Result<Statement, String> {
+    fn parse_statement(&mut self) -> Result<Statement, CompileError> {
+        trace_event!(
+            position = self.position,
+            line = self.tokens.get(self.position).map(|t| t.line),
+            col = self.tokens.get(self.position).map(|t| t.col),
+            token = ?self.current_token(),
+            "parse_statement"
+        );
         match self.current_token() {
             Token::Eom(_) => self.parse_assignment(),
+            Token::Dollar => self.parse_local(),
+            // 식? on its own reads a number and discards it
+            Token::Sik if self.starts_unary(self.position) => Ok(Statement::Eval(self.parse_expr()?)),
             Token::Sik => self.parse_console(),
             Token::Dongtan => self.parse_conditional(),
             Token::Joon => self.parse_goto(),
             Token::Hwaiting => self.parse_return(),
-            _ => Err(format!(
+            // Only an expression reading input does anything when discarded
+            Token::Eo(_) | Token::LParen => Err(self.error(
+                "An expression statement must start with 식? (assign other values with 엄 or print them with 식)",
+            )),
+            // ㅋ only means something after 식 or inside the end marker
+            Token::Kek => Err(self.error("Unexpected ㅋ outside a 식 statement or the end marker")),
+            _ => Err(self.error(format!(
                 "Unexpected token at statement start: {:?}",
                 self.current_token()
-            )),
+            ))),
         }
     }
 
-    fn parse_assignment(&mut self) -> Result<Statement, String> {
+    fn parse_assignment(&mut self) -> Result<Statement, CompileError> {
         // Get variable index from token
         let var_index = match self.current_token().clone() {
             Token::Eom(eo_count) => {
-                // 엄=1, 어엄=2, 어어엄=3, 어어엄어=4, ...
-                // eo_count is the total number of 어s (before and after 엄)
-                let index = eo_count + 1;
+                // 엄=1, 어엄=2, 어어엄=3, ...: eo_count is the number of 어s before
+                // 엄. 어s after 엄 are a separate Eo token starting the value, so
+                // 어어엄어어 assigns var2 to var3.
+                let index = self.check_var_index(eo_count + 1)?;
                 self.advance();
                 index
             },
-            _ => return Err("Expected assignment token (Eom)".to_string()),
+            _ => return Err(self.error("Expected assignment token (Eom)")),
         };
 
-        // Check if it's input (식?)
+        // Check if it's input (식? for a number, 식ㅋ? for a byte)
         if matches!(self.current_token(), Token::Sik) {
             self.advance();
+            let raw_byte = matches!(self.current_token(), Token::Kek);
+            if raw_byte {
+                self.advance();
+            }
             if matches!(self.current_token(), Token::Question) {
                 self.advance();
-                return Ok(Statement::Input { var_index });
+                return if raw_byte {
+                    Ok(Statement::InputChar {
+                        var_index: self.resolve_var(var_index),
+                    })
+                } else {
+                    self.parse_input_count(var_index)
+                };
             } else {
-                return Err("Expected '?' after '식' for input".to_string());
+                return Err(self.error("Expected '?' after '식' for input"));
             }
         }
 
-        // Check if there's a value to assign (could be empty/newline)
-        if matches!(self.current_token(), Token::Newline | Token::EOF) {
-            // Assignment with no value (initialize to 0)
+        // A bare 엄 with nothing after it on the statement clears the variable to 0
+        if matches!(
+            self.current_token(),
+            Token::Newline | Token::EOF | Token::Tilde | Token::Colon | Token::IEotteonSaram
+        ) {
             return Ok(Statement::Assign {
-                var_index,
+                var_index: self.resolve_var(var_index),
                 value: Expr::Number(0),
             });
         }
 
         // Otherwise, parse the value expression
         let value = self.parse_expr()?;
-        Ok(Statement::Assign { var_index, value })
+        Ok(Statement::Assign {
+            var_index: self.resolve_var(var_index),
+            value,
+        })
     }
 
-    fn count_eo_sequence(&mut self) -> usize {
-        // With new token format, Eo already contains the count
-        if let Token::Eo(count) = self.current_token().clone() {
-            self.advance();
-            count
-        } else {
-            0
+    // $ before an assignment in a 동탄 body declares a block-local variable: up
+    // to the end of that body (or else part) its index names a fresh variable,
+    // leaving the one outside untouched. The value is read before the
+    // declaration takes effect, so `$엄어.` starts it at the outer var 1 plus 1.
+    fn parse_local(&mut self) -> Result<Statement, CompileError> {
+        if self.scopes.is_empty() {
+            return Err(self.error("A block-local variable ($) must be declared in a 동탄 body"));
+        }
+        self.advance(); // skip $
+        let Token::Eom(eo_count) = *self.current_token() else {
+            return Err(self.error("Expected an assignment after '$'"));
+        };
+        let source_index = eo_count + 1;
+        let local = self
+            .max_var_index
+            .checked_add(self.locals.len() + 1)
+            .ok_or_else(|| self.error("Too many block-local variables"))?;
+
+        let var_index = local;
+        let stmt = match self.parse_assignment()? {
+            Statement::Assign { value, .. } => Statement::Assign { var_index, value },
+            Statement::Input { .. } => Statement::Input { var_index },
+            Statement::InputChar { .. } => Statement::InputChar { var_index },
+            _ => return Err(self.error("식? with a count can't declare a block-local variable")),
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(source_index, local);
+        }
+        self.locals.insert(local, source_index);
+        Ok(stmt)
+    }
+
+    // Block-local variables of a 동탄 body go out of scope at its else part
+    fn end_scope(&mut self) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.clear();
         }
     }
 
-    fn parse_console(&mut self) -> Result<Statement, String> {
+    // 엄식? optionally followed by how many integers to read into consecutive
+    // variables, as a dot run or an @ literal: 엄식?... fills vars 1 to 3
+    fn parse_input_count(&mut self, var_index: usize) -> Result<Statement, CompileError> {
+        let count = match *self.current_token() {
+            Token::Dot(run) => run as i64,
+            Token::Number(value) => value,
+            _ => {
+                return Ok(Statement::Input {
+                    var_index: self.resolve_var(var_index),
+                });
+            },
+        };
+        if count < 1 {
+            return Err(self.error(format!("Input count must be at least 1, got {}", count)));
+        }
+        self.check_var_index(var_index.saturating_add(count as usize - 1))?;
+        if count > 1 && (var_index..var_index + count as usize).any(|index| self.resolve_var(index) != index) {
+            return Err(self.error("식? with a count can't read into a block-local variable"));
+        }
+        self.advance();
+        Ok(if count == 1 {
+            Statement::Input {
+                var_index: self.resolve_var(var_index),
+            }
+        } else {
+            Statement::InputMany {
+                var_index,
+                count: count as usize,
+            }
+        })
+    }
+
+    fn parse_console(&mut self) -> Result<Statement, CompileError> {
         self.advance(); // skip 식
 
         match self.current_token() {
-            Token::Question => {
-                // Input was already handled in parse_assignment
-                Err("식? should be part of assignment".to_string())
-            },
-            Token::Exclamation => {
-                self.advance();
-                // Parse expression before !
-                // We need to backtrack - the expression is before !
-                // Actually식{expr}! means print expr
-                // Let's reparse this properly
-                Err("PrintNum needs redesign".to_string())
-            },
+            Token::Exclamation => Err(self.error("식! requires an expression to print")),
             Token::Kek => {
                 self.advance();
                 Ok(Statement::PrintNewline)
             },
+            Token::Str(text) => {
+                let text = text.clone();
+                self.advance();
+                Ok(Statement::PrintStr(text))
+            },
             _ => {
-                // 식{number}ㅋ or 식{expr}!
-                let expr = self.parse_expr()?;
+                // 식{number}ㅋ or 식{expr}!, where spaces around the expression are padding
+                self.skip_spaces();
+                self.in_print = true;
+                let expr = self.parse_expr();
+                self.in_print = false;
+                let expr = expr?;
+                self.skip_spaces();
                 match self.current_token() {
                     Token::Kek => {
                         self.advance();
@@ -173,143 +436,439 @@ impl Parser {
                     },
                     Token::Exclamation => {
                         self.advance();
-                        Ok(Statement::PrintNum(expr))
+                        self.parse_print_radix(expr)
                     },
-                    _ => Err(format!(
+                    _ => Err(self.error(format!(
                         "Expected 'ㅋ' or '!' after expression in console statement, found {:?}",
                         self.current_token()
-                    )),
+                    ))),
                 }
             },
         }
     }
 
-    fn parse_conditional(&mut self) -> Result<Statement, String> {
+    // 식{expr}! optionally followed by a radix, as a dot run or an @ literal:
+    // 식어!@16 prints in hex, 식어!.. in binary
+    fn parse_print_radix(&mut self, expr: Expr) -> Result<Statement, CompileError> {
+        let radix = match *self.current_token() {
+            Token::Dot(run) => run as i64,
+            Token::Number(value) => value,
+            _ => return Ok(Statement::PrintNum(expr)),
+        };
+        if !(2..=16).contains(&radix) {
+            return Err(self.error(format!("Print radix must be between 2 and 16, got {}", radix)));
+        }
+        self.advance();
+        Ok(Statement::PrintNumRadix {
+            expr,
+            radix: radix as u32,
+        })
+    }
+
+    fn parse_conditional(&mut self) -> Result<Statement, CompileError> {
+        let starts_line = self.position == 0
+            || matches!(self.tokens[self.position - 1].token, Token::Newline | Token::Eotteohke);
         self.advance(); // skip 동탄
 
         let condition = self.parse_expr()?;
         self.expect(Token::Question)?;
 
-        // Parse the body until newline or tilde
+        // A second '?' makes it a loop, which is lowered to a goto back to its
+        // own line, so it must start that line and can't sit inside a 동탄 body
+        if matches!(self.current_token(), Token::Question) {
+            if !starts_line {
+                return Err(self.error("A while loop must start its own line"));
+            }
+            if !self.scopes.is_empty() {
+                return Err(self.error("A while loop can't be nested in a 동탄 body"));
+            }
+            self.advance();
+            return self.parse_while(condition);
+        }
+
+        self.scopes.push(HashMap::new());
+        let result = self.parse_conditional_body(condition);
+        self.scopes.pop();
+        result
+    }
+
+    fn parse_conditional_body(&mut self, condition: Expr) -> Result<Statement, CompileError> {
+        // A newline right after '?' opens a block that runs until ';'
+        if matches!(self.current_token(), Token::Newline) {
+            let (body, else_body) = self.parse_conditional_block()?;
+            return Ok(Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            });
+        }
+
+        // Parse the body until newline, tilde, or the else marker
         let mut body = Vec::new();
         while !matches!(
             self.current_token(),
-            Token::Newline | Token::Tilde | Token::EOF | Token::IEotteonSaram
+            Token::Newline | Token::Tilde | Token::EOF | Token::IEotteonSaram | Token::Colon
         ) {
             body.push(self.parse_statement()?);
         }
 
-        Ok(Statement::Conditional { condition, body })
+        // Optional else body after ':'
+        let mut else_body = Vec::new();
+        if matches!(self.current_token(), Token::Colon) {
+            self.advance();
+            self.end_scope();
+            while !matches!(
+                self.current_token(),
+                Token::Newline | Token::Tilde | Token::EOF | Token::IEotteonSaram | Token::Colon
+            ) {
+                else_body.push(self.parse_statement()?);
+            }
+        }
+
+        Ok(Statement::Conditional {
+            condition,
+            body,
+            else_body,
+        })
+    }
+
+    // Block form:
+    //   동탄{expr}?
+    //   {statements}
+    //   :
+    //   {else statements}
+    //   ;
+    // The ':' line is optional, and blocks may nest.
+    fn parse_conditional_block(&mut self) -> Result<(Vec<Statement>, Vec<Statement>), CompileError> {
+        let mut body = Vec::new();
+        let mut else_body = Vec::new();
+        let mut in_else = false;
+
+        loop {
+            self.skip_newlines();
+            match self.current_token() {
+                Token::Semicolon => {
+                    self.advance();
+                    return Ok((body, else_body));
+                },
+                Token::Colon if !in_else => {
+                    self.advance();
+                    self.end_scope();
+                    in_else = true;
+                },
+                Token::Tilde => self.advance(),
+                Token::EOF | Token::IEotteonSaram => {
+                    return Err(self.error("Unterminated 동탄 block: expected ';'"));
+                },
+                _ => {
+                    let stmt = self.parse_statement()?;
+                    if in_else {
+                        else_body.push(stmt);
+                    } else {
+                        body.push(stmt);
+                    }
+                },
+            }
+        }
     }
 
-    fn parse_goto(&mut self) -> Result<Statement, String> {
+    // 동탄{expr}??{stmt} runs its body over and over while the expression is
+    // zero. Inline, the body is the rest of the line, `~` segments included; a
+    // newline right after '??' opens a block that runs until ';' and may hold
+    // further loops. Either form must end its line, since leaving the loop
+    // jumps to the next line.
+    fn parse_while(&mut self, condition: Expr) -> Result<Statement, CompileError> {
+        let mut body = Vec::new();
+        let end_line = if matches!(self.current_token(), Token::Newline) {
+            loop {
+                self.skip_newlines();
+                match self.current_token() {
+                    Token::Semicolon => {
+                        let end_line = self.current_span().line;
+                        self.advance();
+                        break end_line;
+                    },
+                    Token::Tilde => self.advance(),
+                    Token::Colon => return Err(self.error("A while loop has no else part")),
+                    Token::EOF | Token::IEotteonSaram => {
+                        return Err(self.error("Unterminated while block: expected ';'"));
+                    },
+                    _ => {
+                        let span = self.current_span();
+                        body.push((self.parse_statement()?, span));
+                    },
+                }
+            }
+        } else {
+            while !matches!(
+                self.current_token(),
+                Token::Newline | Token::EOF | Token::IEotteonSaram | Token::Colon
+            ) {
+                if matches!(self.current_token(), Token::Tilde) {
+                    self.advance();
+                    continue;
+                }
+                let span = self.current_span();
+                body.push((self.parse_statement()?, span));
+            }
+            if matches!(self.current_token(), Token::Colon) {
+                return Err(self.error("A while loop has no else part"));
+            }
+            self.tokens[self.position - 1].line
+        };
+
+        if !matches!(
+            self.current_token(),
+            Token::Newline | Token::EOF | Token::IEotteonSaram
+        ) {
+            return Err(self.error("A while loop must end its line"));
+        }
+        Ok(Statement::While {
+            condition,
+            body,
+            end_line,
+        })
+    }
+
+    fn parse_goto(&mut self) -> Result<Statement, CompileError> {
+        let goto_line = self.tokens.get(self.position).map_or(1, |t| t.line);
         self.advance(); // skip 준
         let line_expr = self.parse_expr()?;
 
-        // Evaluate expression to get line number
+        // Constant targets become a plain jump; anything else is resolved at runtime
         match Self::eval_const_expr(&line_expr) {
-            Some(line) if line > 0 => Ok(Statement::Goto(line as usize)),
-            Some(line) => Err(format!("Goto line number must be positive, got {}", line)),
-            None => Err("Goto requires a constant expression (no variables)".to_string()),
+            Ok(Some(line)) if line > 0 => Ok(Statement::Goto {
+                target: line as usize,
+                line: goto_line,
+            }),
+            Ok(Some(line)) => Err(self.error(format!("Goto line number must be positive, got {}", line))),
+            Ok(None) => Ok(Statement::GotoDynamic(line_expr)),
+            Err(ConstOverflow) => Err(self.error("goto target expression overflows")),
         }
     }
 
-    fn eval_const_expr(expr: &Expr) -> Option<i64> {
+    // The value of an expression without variables: `Ok(None)` when it reads a
+    // variable (or takes a remainder by zero), `Err` when the arithmetic overflows
+    fn eval_const_expr(expr: &Expr) -> Result<Option<i64>, ConstOverflow> {
+        let binary = |l: &Expr, r: &Expr, op: fn(i64, i64) -> Option<i64>| {
+            let Some(left) = Self::eval_const_expr(l)? else {
+                return Ok(None);
+            };
+            let Some(right) = Self::eval_const_expr(r)? else {
+                return Ok(None);
+            };
+            op(left, right).map(Some).ok_or(ConstOverflow)
+        };
         match expr {
-            Expr::Number(n) => Some(*n),
-            Expr::Var(_) => None, // Variables are not constant
-            Expr::Add(l, r) => {
-                let left = Self::eval_const_expr(l)?;
-                let right = Self::eval_const_expr(r)?;
-                Some(left + right)
+            Expr::Number(n) => Ok(Some(*n)),
+            Expr::Var(_) | Expr::InputNum => Ok(None), // Variables and input are not constant
+            Expr::Add(l, r) => binary(l, r, i64::checked_add),
+            Expr::Sub(l, r) => binary(l, r, i64::checked_sub),
+            Expr::Mul(l, r) => binary(l, r, i64::checked_mul),
+            Expr::Mod(l, r) => {
+                // Modulo by zero is not a constant
+                if Self::eval_const_expr(r)? == Some(0) {
+                    return Ok(None);
+                }
+                binary(l, r, i64::checked_rem)
             },
-            Expr::Sub(l, r) => {
-                let left = Self::eval_const_expr(l)?;
-                let right = Self::eval_const_expr(r)?;
-                Some(left - right)
+            Expr::Pow(base, exp) => match Self::eval_const_expr(base)? {
+                Some(base) => base.checked_pow(*exp).map(Some).ok_or(ConstOverflow),
+                None => Ok(None),
             },
-            Expr::Mul(l, r) => {
-                let left = Self::eval_const_expr(l)?;
-                let right = Self::eval_const_expr(r)?;
-                Some(left * right)
+            Expr::Neg(operand) => match Self::eval_const_expr(operand)? {
+                Some(value) => value.checked_neg().map(Some).ok_or(ConstOverflow),
+                None => Ok(None),
             },
+            Expr::Eq(l, r) => binary(l, r, |a, b| Some((a == b) as i64)),
+            Expr::Lt(l, r) => binary(l, r, |a, b| Some((a < b) as i64)),
+            Expr::Gt(l, r) => binary(l, r, |a, b| Some((a > b) as i64)),
         }
     }
 
-    fn parse_return(&mut self) -> Result<Statement, String> {
+    fn parse_return(&mut self) -> Result<Statement, CompileError> {
         self.advance(); // skip 화이팅
         self.expect(Token::Exclamation)?;
         let value = self.parse_expr()?;
         Ok(Statement::Return(value))
     }
 
-    fn parse_expr(&mut self) -> Result<Expr, String> {
-        self.parse_multiplicative()
+    fn parse_expr(&mut self) -> Result<Expr, CompileError> {
+        self.parse_comparison()
     }
 
-    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_additive()?;
+    fn parse_comparison(&mut self) -> Result<Expr, CompileError> {
+        let mut left = self.parse_multiplicative()?;
 
-        // Space means multiplication
-        while matches!(self.current_token(), Token::Space) {
-            self.advance(); // consume space
-            let right = self.parse_additive()?;
-            left = Expr::Mul(Box::new(left), Box::new(right));
+        // Comparisons bind loosest, so `어 어=....` compares the product
+        loop {
+            let make: fn(Box<Expr>, Box<Expr>) -> Expr = match self.current_token() {
+                Token::Equals => Expr::Eq,
+                Token::Less => Expr::Lt,
+                Token::Greater => Expr::Gt,
+                _ => break,
+            };
+            self.advance(); // consume the operator
+            let right = self.parse_multiplicative()?;
+            left = make(Box::new(left), Box::new(right));
         }
 
         Ok(left)
     }
 
-    fn parse_additive(&mut self) -> Result<Expr, String> {
-        let mut dots = 0i64;
-        let mut commas = 0i64;
-        let mut has_var = false;
-        let mut var_index = 0;
+    fn parse_multiplicative(&mut self) -> Result<Expr, CompileError> {
+        let mut left = self.parse_power()?;
 
-        // Count dots and commas, check for variable
+        // Space means multiplication, % means modulo
         loop {
             match self.current_token() {
-                Token::Dot => {
-                    dots += 1;
+                Token::Space => {
+                    if self.in_print && self.spaces_before_print_end() {
+                        break;
+                    }
+                    // Spaces with no operand after them (before `~`, `?`, the end of
+                    // the line...) trail the expression instead of multiplying it
+                    if !self.operand_after_spaces() {
+                        while matches!(self.current_token(), Token::Space) {
+                            self.advance();
+                        }
+                        break;
+                    }
+                    self.advance(); // consume space
+                    let right = self.parse_power()?;
+                    left = Expr::Mul(Box::new(left), Box::new(right));
+                },
+                Token::Percent => {
+                    self.advance(); // consume %
+                    let right = self.parse_power()?;
+                    left = Expr::Mod(Box::new(left), Box::new(right));
+                },
+                _ => break,
+            }
+        }
+
+        Ok(left)
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, CompileError> {
+        let base = self.parse_additive()?;
+        if !matches!(self.current_token(), Token::Caret) {
+            return Ok(base);
+        }
+        self.advance(); // consume ^
+
+        // The exponent is unrolled into multiplies, so it must be known now
+        let exponent = self.parse_additive()?;
+        match Self::eval_const_expr(&exponent) {
+            Ok(Some(exp)) if exp < 0 => Err(self.error(format!("Exponent must not be negative, got {}", exp))),
+            Ok(Some(exp)) => match u32::try_from(exp) {
+                Ok(exp) => Ok(Expr::Pow(Box::new(base), exp)),
+                Err(_) => Err(self.error(format!("Exponent {} is too large", exp))),
+            },
+            Ok(None) => Err(self.error("Exponent must be a constant")),
+            Err(ConstOverflow) => Err(self.error("Exponent expression overflows")),
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, CompileError> {
+        let mut left: Option<Expr> = None;
+
+        // Fold dot runs (+n), comma runs (-n), @ literals and operands left to right
+        loop {
+            match self.current_token() {
+                &Token::Dot(run) | &Token::Comma(run) => {
+                    let negative = matches!(self.current_token(), Token::Comma(_));
+                    let count = run as i64;
                     self.advance();
+                    left = Some(match left {
+                        // Keep literal-only runs a single number, e.g. ..., = 2. A sum
+                        // that would overflow stays an Add/Sub, so goto targets can
+                        // report it and everything else wraps at runtime as before.
+                        None => Expr::Number(if negative { -count } else { count }),
+                        Some(Expr::Number(n)) if negative && n.checked_sub(count).is_some() => {
+                            Expr::Number(n - count)
+                        },
+                        Some(Expr::Number(n)) if !negative && n.checked_add(count).is_some() => {
+                            Expr::Number(n + count)
+                        },
+                        Some(expr) if negative => Expr::Sub(Box::new(expr), Box::new(Expr::Number(count))),
+                        Some(expr) => Expr::Add(Box::new(expr), Box::new(Expr::Number(count))),
+                    });
                 },
-                Token::Comma => {
-                    commas += 1;
+                Token::Number(value) => {
+                    // A compact literal adds like a run of that many dots
+                    let value = *value;
                     self.advance();
+                    left = Some(match left {
+                        None => Expr::Number(value),
+                        Some(Expr::Number(n)) if n.checked_add(value).is_some() => Expr::Number(n + value),
+                        Some(expr) => Expr::Add(Box::new(expr), Box::new(Expr::Number(value))),
+                    });
                 },
-                Token::Eo(_) => {
-                    // Variable reference - can appear before or after dots/commas
-                    if !has_var {
-                        var_index = self.count_eo_sequence();
-                        has_var = true;
-                    } else {
-                        // Two variables in a row - stop
-                        break;
-                    }
+                _ if self.starts_unary(self.position) => {
+                    let operand = self.parse_unary()?;
+                    left = Some(match left {
+                        None => operand,
+                        Some(expr) => Expr::Add(Box::new(expr), Box::new(operand)),
+                    });
                 },
                 _ => break,
             }
         }
 
-        // Build expression: variable + number OR just number OR just variable
-        let base_expr = if has_var {
-            Expr::Var(var_index)
-        } else if dots > 0 || commas > 0 {
-            return Ok(Expr::Number(dots - commas));
-        } else {
-            return Err(format!(
+        left.ok_or_else(|| {
+            self.error(format!(
                 "Expected expression (dots, commas, or variable), found {:?}",
                 self.current_token()
-            ));
-        };
+            ))
+        })
+    }
 
-        // Add dots/commas to variable if present
-        if dots > 0 || commas > 0 {
-            let number = Expr::Number(dots - commas);
-            Ok(Expr::Add(Box::new(base_expr), Box::new(number)))
-        } else {
-            Ok(base_expr)
+    // A variable, @ literal or group, negated by each leading '-'
+    fn parse_unary(&mut self) -> Result<Expr, CompileError> {
+        if matches!(self.current_token(), Token::Minus) {
+            self.advance();
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
         }
+        self.parse_primary()
     }
 
+    fn parse_primary(&mut self) -> Result<Expr, CompileError> {
+        match self.current_token() {
+            Token::Eo(count) => {
+                let index = self.check_var_index(*count)?;
+                self.advance();
+                Ok(Expr::Var(self.resolve_var(index)))
+            },
+            Token::Sik => {
+                self.advance();
+                if !matches!(self.current_token(), Token::Question) {
+                    return Err(self.error("Expected '?' after '식' for input"));
+                }
+                self.advance();
+                Ok(Expr::InputNum)
+            },
+            Token::Number(value) => {
+                let value = *value;
+                self.advance();
+                Ok(Expr::Number(value))
+            },
+            Token::LParen => {
+                self.advance(); // skip (
+                let expr = self.parse_expr()?;
+                if !matches!(self.current_token(), Token::RParen) {
+                    return Err(self.error(format!(
+                        "Expected ')' to close group, found {:?}",
+                        self.current_token()
+                    )));
+                }
+                self.advance();
+                Ok(expr)
+            },
+            _ => Err(self.error(format!(
+                "Expected variable, @ literal or '(', found {:?}",
+                self.current_token()
+            ))),
+        }
+    }
 }
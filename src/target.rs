@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A machine Lamina can generate assembly for: an architecture and the OS
+/// whose calling and object conventions the assembly follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    X86_64Linux,
+    X86_64Macos,
+    X86_64Windows,
+    X86_64Unknown,
+    Aarch64Linux,
+    Aarch64Macos,
+    Aarch64Windows,
+    Aarch64Unknown,
+}
+
+impl Target {
+    /// Every target, in the order `--target`'s error message lists them.
+    pub const ALL: [Target; 8] = [
+        Target::X86_64Linux,
+        Target::X86_64Macos,
+        Target::X86_64Windows,
+        Target::X86_64Unknown,
+        Target::Aarch64Linux,
+        Target::Aarch64Macos,
+        Target::Aarch64Windows,
+        Target::Aarch64Unknown,
+    ];
+
+    /// The name Lamina's target-specific entry points expect, e.g. `x86_64_linux`.
+    /// `Display` and `FromStr` use the same names.
+    pub fn lamina_name(self) -> &'static str {
+        match self {
+            Target::X86_64Linux => "x86_64_linux",
+            Target::X86_64Macos => "x86_64_macos",
+            Target::X86_64Windows => "x86_64_windows",
+            Target::X86_64Unknown => "x86_64_unknown",
+            Target::Aarch64Linux => "aarch64_linux",
+            Target::Aarch64Macos => "aarch64_macos",
+            Target::Aarch64Windows => "aarch64_windows",
+            Target::Aarch64Unknown => "aarch64_unknown",
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.lamina_name())
+    }
+}
+
+impl FromStr for Target {
+    type Err = UnknownTarget;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Target::ALL
+            .into_iter()
+            .find(|target| target.lamina_name() == name)
+            .ok_or_else(|| UnknownTarget(name.to_string()))
+    }
+}
+
+/// A target name that isn't one of `Target::ALL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTarget(pub String);
+
+impl fmt::Display for UnknownTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = Target::ALL.iter().map(|target| target.lamina_name()).collect();
+        write!(f, "unknown target '{}' (valid targets: {})", self.0, names.join(", "))
+    }
+}
+
+impl Error for UnknownTarget {}
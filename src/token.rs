@@ -1,4 +1,5 @@
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
     // Keywords
     Eotteohke,         // 어떻게 - program start
@@ -11,10 +12,21 @@ pub enum Token {
     Hwaiting,      // 화이팅 - return/exit
 
     // Operators
-    Dot,   // . - increment
-    Comma, // , - decrement
-    Space, // (space) - multiply
-    Tilde, // ~ - line separator (for one-line code)
+    Dot(usize),   // . run - increment by the run length
+    Comma(usize), // , run - decrement by the run length
+    Space,   // (space) - multiply
+    Tilde,   // ~ - line separator (for one-line code)
+    Percent, // % - modulo
+    Colon,   // : - else branch of a conditional
+    Semicolon, // ; - end of a multi-line conditional block
+    LParen,  // ( - start of a grouped expression
+    RParen,  // ) - end of a grouped expression
+    Caret,   // ^ - power with a constant exponent
+    Minus,   // - - negate the following operand
+    Equals,  // = - 1 if both sides are equal, else 0
+    Less,    // < - 1 if the left side is smaller, else 0
+    Greater, // > - 1 if the left side is larger, else 0
+    Dollar,  // $ - declare a block-local variable in a 동탄 body
 
     // Console
     Question,    // ? - input
@@ -22,7 +34,8 @@ pub enum Token {
     Kek,         // ㅋ - print char / end marker
 
     // Literal
-    Number(i64), // calculated number from dots and commas
+    Number(i64), // compact decimal literal: @1000000
+    Str(String), // string literal, printed byte by byte: "Hi\n"
 
     // Special
     Newline,
@@ -30,6 +43,7 @@ pub enum Token {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenWithPos {
     pub token: Token,
     pub line: usize,
@@ -0,0 +1,28 @@
+// Tracing hooks for the `trace` feature. Without it both macros expand to
+// nothing, so the default build pays nothing for them.
+
+// Emits a `tracing::trace!` event
+#[cfg(feature = "trace")]
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        tracing::trace!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_event {
+    ($($arg:tt)*) => {};
+}
+
+// Enters a `tracing::debug_span!` until the end of the enclosing block
+#[cfg(feature = "trace")]
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_span {
+    ($($arg:tt)*) => {};
+}
@@ -0,0 +1,96 @@
+use crate::ast::{Expr, Program, Span, Statement};
+use crate::codegen::CodeGenerator;
+use crate::error::CompileError;
+use crate::lower::lower_loops;
+use crate::parser::DEFAULT_MAX_VAR_INDEX;
+use crate::visit::Visitor;
+use std::collections::BTreeMap;
+
+/// Runs the static checks a program must pass before codegen and returns
+/// every error found, in source order:
+///
+/// - a goto targets a line that doesn't exist;
+/// - a variable index is 0 or above `DEFAULT_MAX_VAR_INDEX`, other than a
+///   block-local variable's fresh index;
+/// - a conditional has neither a body nor an else part;
+/// - a modulo divides by an expression that is constant zero.
+///
+/// `compile_umjunsik` calls this between parsing and codegen. Loops are
+/// checked in their lowered form, so the gotos they become are checked too.
+pub fn validate(program: &Program) -> Result<(), Vec<CompileError>> {
+    let program = &*lower_loops(program);
+    let max_line = program.statements.iter().map(|(_, line)| *line).max().unwrap_or(1);
+
+    let mut checker = Checker {
+        locals: &program.locals,
+        span: Span { line: 1, col: 1 },
+        errors: Vec::new(),
+    };
+    for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+        checker.span = program.span(idx);
+        if let Err(err) = CodeGenerator::check_goto_targets(stmt, max_line, checker.span) {
+            checker.errors.push(err);
+        }
+        checker.visit_statement(stmt);
+    }
+
+    if checker.errors.is_empty() { Ok(()) } else { Err(checker.errors) }
+}
+
+// Collects errors for the statement at `span`, nested statements included
+struct Checker<'a> {
+    locals: &'a BTreeMap<usize, usize>,
+    span: Span,
+    errors: Vec<CompileError>,
+}
+
+impl Checker<'_> {
+    fn error(&mut self, message: String) {
+        self.errors.push(CompileError::Codegen {
+            span: Some(self.span),
+            message,
+        });
+    }
+
+    fn check_var_index(&mut self, var_index: usize) {
+        if var_index == 0 || (var_index > DEFAULT_MAX_VAR_INDEX && !self.locals.contains_key(&var_index)) {
+            self.error(format!(
+                "variable index {} is outside 1 to {}",
+                var_index, DEFAULT_MAX_VAR_INDEX
+            ));
+        }
+    }
+}
+
+impl Visitor for Checker<'_> {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Assign { var_index, .. }
+            | Statement::Input { var_index }
+            | Statement::InputChar { var_index } => self.check_var_index(*var_index),
+            // The range only needs its two ends checked
+            Statement::InputMany { var_index, count } => {
+                self.check_var_index(*var_index);
+                if *count > 1 {
+                    self.check_var_index(var_index + count - 1);
+                }
+            },
+            Statement::Conditional { body, else_body, .. } if body.is_empty() && else_body.is_empty() => {
+                self.error("conditional has neither a body nor an else part".to_string());
+            },
+            _ => {},
+        }
+        self.walk_statement(stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Var(index) => self.check_var_index(*index),
+            Expr::Mod(_, divisor) if matches!(CodeGenerator::fold_constants(divisor, false), Expr::Number(0)) => {
+                self.error("modulo by constant zero".to_string());
+            },
+            _ => {},
+        }
+        self.walk_expr(expr);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::ast::{Expr, Program, Statement};
+
+/// Read-only traversal over a parsed program.
+///
+/// Every method has a default, so an analysis only overrides the nodes it cares
+/// about. An overriding `visit_*` method should call the matching `walk_*`
+/// method to keep descending into children.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        self.walk_program(program);
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement) {
+        self.walk_statement(stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.walk_expr(expr);
+    }
+
+    fn walk_program(&mut self, program: &Program) {
+        for (stmt, _) in &program.statements {
+            self.visit_statement(stmt);
+        }
+    }
+
+    fn walk_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Assign { value, .. } => self.visit_expr(value),
+            Statement::PrintNum(expr)
+            | Statement::PrintNumRadix { expr, .. }
+            | Statement::PrintChar(expr)
+            | Statement::GotoDynamic(expr)
+            | Statement::Return(expr)
+            | Statement::Eval(expr) => self.visit_expr(expr),
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                self.visit_expr(condition);
+                for s in body.iter().chain(else_body) {
+                    self.visit_statement(s);
+                }
+            },
+            Statement::While { condition, body, .. } => {
+                self.visit_expr(condition);
+                for (s, _) in body {
+                    self.visit_statement(s);
+                }
+            },
+            Statement::Input { .. }
+            | Statement::InputMany { .. }
+            | Statement::InputChar { .. }
+            | Statement::PrintNewline
+            | Statement::PrintStr(_)
+            | Statement::Goto { .. } => {},
+        }
+    }
+
+    fn walk_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Number(_) | Expr::Var(_) | Expr::InputNum => {},
+            Expr::Add(left, right)
+            | Expr::Sub(left, right)
+            | Expr::Mul(left, right)
+            | Expr::Mod(left, right)
+            | Expr::Eq(left, right)
+            | Expr::Lt(left, right)
+            | Expr::Gt(left, right) => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            },
+            Expr::Pow(base, _) | Expr::Neg(base) => self.visit_expr(base),
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use crate::ast::{Expr, Program, Statement};
+use crate::codegen::CodeGenerator;
+use crate::error::CompileError;
+use crate::lower::lower_loops;
+
+/// Lowers a program to a WebAssembly text module.
+///
+/// The module imports `env.readbyte` (returns the next stdin byte, or -1 at the
+/// end of input), `env.writebyte` (writes one byte) and `env.print` (writes a
+/// number followed by a newline), and exports `main`, which returns the exit value.
+///
+/// Each statement becomes one arm of a `br_table` dispatch loop indexed by `$pc`,
+/// so falling through to the next statement is free and a goto just sets `$pc`
+/// and re-enters the loop.
+pub fn generate_wat(program: &Program) -> Result<String, CompileError> {
+    let program = &*lower_loops(program);
+    let max_line = program.statements.iter().map(|(_, line)| *line).max().unwrap_or(1);
+    for (idx, (stmt, _)) in program.statements.iter().enumerate() {
+        CodeGenerator::check_goto_targets(stmt, max_line, program.span(idx))?;
+    }
+
+    let mut generator = WatGenerator {
+        output: String::new(),
+        lines: program.statements.iter().map(|(_, line)| *line).collect(),
+        max_line,
+        block_counter: 0,
+        pow_locals: 0,
+    };
+    generator.generate(program);
+    Ok(generator.output)
+}
+
+struct WatGenerator {
+    output: String,
+    lines: Vec<usize>, // Source line of each statement, in order
+    max_line: usize,
+    block_counter: usize,
+    pow_locals: usize, // Scratch locals holding the base of each Expr::Pow
+}
+
+impl WatGenerator {
+    fn generate(&mut self, program: &Program) {
+        self.output.push_str("(module\n");
+        self.output
+            .push_str("  (import \"env\" \"readbyte\" (func $readbyte (result i64)))\n");
+        self.output
+            .push_str("  (import \"env\" \"writebyte\" (func $writebyte (param i64)))\n");
+        self.output.push_str("  (import \"env\" \"print\" (func $print (param i64)))\n");
+        self.output.push_str("  (func $main (export \"main\") (result i64)\n");
+
+        // $pc selects the statement to resume at; the rest are input/goto/print scratch
+        self.output.push_str("    (local $pc i32)\n");
+        for var_idx in CodeGenerator::collect_used_variables(program) {
+            self.output.push_str(&format!("    (local $var_{} i64)\n", var_idx));
+        }
+        self.output.push_str("    (local $acc i64)\n");
+        self.output.push_str("    (local $byte i64)\n");
+        self.output.push_str("    (local $target i64)\n");
+        let pow_locals_at = self.output.len();
+
+        // Blocks are opened outermost-last so that branching to $s{k} lands on statement k.
+        // They aren't indented by depth, which would grow with the program.
+        self.output.push_str("    loop $dispatch\n");
+        self.output.push_str("    block $exit\n");
+        for idx in (0..program.statements.len()).rev() {
+            self.output.push_str(&format!("    block $s{}\n", idx));
+        }
+        let arms: Vec<String> = (0..program.statements.len()).map(|idx| format!("$s{}", idx)).collect();
+        self.output.push_str("    local.get $pc\n");
+        if arms.is_empty() {
+            self.output.push_str("    br_table $exit\n");
+        } else {
+            self.output.push_str(&format!("    br_table {} $exit\n", arms.join(" ")));
+        }
+
+        for (stmt, line) in &program.statements {
+            self.output.push_str("    end\n");
+            self.output.push_str(&format!("    ;; line {}\n", line));
+            self.generate_statement(stmt);
+        }
+
+        self.output.push_str("    end\n"); // $exit
+        self.output.push_str("    end\n"); // $dispatch
+        self.output.push_str("    i64.const 0\n");
+        self.output.push_str("  )\n");
+        self.output.push_str(")\n");
+
+        // Only known once the body has been generated
+        let pow_locals: String = (0..self.pow_locals)
+            .map(|idx| format!("    (local $pow_{} i64)\n", idx))
+            .collect();
+        self.output.insert_str(pow_locals_at, &pow_locals);
+    }
+
+    // Sign, then lowercase digits of the non-positive magnitude (which can hold
+    // i64::MIN) in $acc. The leading digits come from $acc / radix, whose place
+    // values (kept in $target) always fit; the last digit is written on its own.
+    fn generate_radix_print(&mut self, expr: &Expr, radix: u32) {
+        let id = self.block_counter;
+        self.block_counter += 1;
+        self.generate_expr(expr);
+        self.output.push_str("    local.tee $acc\n");
+        self.output.push_str("    i64.const 0\n");
+        self.output.push_str("    i64.lt_s\n");
+        self.output.push_str("    if\n");
+        self.output.push_str("    i64.const 45\n");
+        self.output.push_str("    call $writebyte\n");
+        self.output.push_str("    else\n");
+        self.output.push_str("    i64.const 0\n");
+        self.output.push_str("    local.get $acc\n");
+        self.output.push_str("    i64.sub\n");
+        self.output.push_str("    local.set $acc\n");
+        self.output.push_str("    end\n");
+
+        self.output.push_str("    local.get $acc\n");
+        self.output.push_str(&format!("    i64.const {}\n", radix));
+        self.output.push_str("    i64.div_s\n");
+        self.output.push_str("    i64.const 0\n");
+        self.output.push_str("    i64.lt_s\n");
+        self.output.push_str("    if\n");
+        self.output.push_str("    i64.const 1\n");
+        self.output.push_str("    local.set $target\n");
+        self.output.push_str(&format!("    block $radix_scaled_{}\n", id));
+        self.output.push_str(&format!("    loop $radix_scale_{}\n", id));
+        self.output.push_str("    local.get $acc\n");
+        self.output.push_str(&format!("    i64.const {}\n", radix));
+        self.output.push_str("    i64.div_s\n");
+        self.output.push_str("    local.get $target\n");
+        self.output.push_str("    i64.div_s\n");
+        self.output.push_str(&format!("    i64.const -{}\n", radix));
+        self.output.push_str("    i64.gt_s\n");
+        self.output.push_str(&format!("    br_if $radix_scaled_{}\n", id));
+        self.output.push_str("    local.get $target\n");
+        self.output.push_str(&format!("    i64.const {}\n", radix));
+        self.output.push_str("    i64.mul\n");
+        self.output.push_str("    local.set $target\n");
+        self.output.push_str(&format!("    br $radix_scale_{}\n", id));
+        self.output.push_str("    end\n");
+        self.output.push_str("    end\n");
+        self.output.push_str(&format!("    loop $radix_digit_{}\n", id));
+        self.output.push_str("    local.get $acc\n");
+        self.output.push_str(&format!("    i64.const {}\n", radix));
+        self.output.push_str("    i64.div_s\n");
+        self.output.push_str("    local.get $target\n");
+        self.output.push_str("    i64.div_s\n");
+        self.output.push_str(&format!("    i64.const {}\n", radix));
+        self.output.push_str("    i64.rem_s\n");
+        self.generate_digit_write();
+        self.output.push_str("    local.get $target\n");
+        self.output.push_str(&format!("    i64.const {}\n", radix));
+        self.output.push_str("    i64.div_s\n");
+        self.output.push_str("    local.tee $target\n");
+        self.output.push_str("    i64.const 0\n");
+        self.output.push_str("    i64.gt_s\n");
+        self.output.push_str(&format!("    br_if $radix_digit_{}\n", id));
+        self.output.push_str("    end\n");
+        self.output.push_str("    end\n");
+
+        self.output.push_str("    local.get $acc\n");
+        self.output.push_str(&format!("    i64.const {}\n", radix));
+        self.output.push_str("    i64.rem_s\n");
+        self.generate_digit_write();
+        self.output.push_str("    i64.const 10\n");
+        self.output.push_str("    call $writebyte\n");
+    }
+
+    // Writes the negated digit on the stack as '0' - digit, plus the gap
+    // between '9' and 'a' for digits past 9
+    fn generate_digit_write(&mut self) {
+        self.output.push_str("    local.set $byte\n");
+        self.output.push_str("    i64.const 48\n");
+        self.output.push_str("    local.get $byte\n");
+        self.output.push_str("    i64.sub\n");
+        self.output.push_str("    local.get $byte\n");
+        self.output.push_str("    i64.const -9\n");
+        self.output.push_str("    i64.lt_s\n");
+        self.output.push_str("    i64.extend_i32_u\n");
+        self.output.push_str("    i64.const 39\n");
+        self.output.push_str("    i64.mul\n");
+        self.output.push_str("    i64.add\n");
+        self.output.push_str("    call $writebyte\n");
+    }
+
+    // Jump to the first statement on or after `line`; past the last one,
+    // $pc falls into the br_table default and the program ends
+    fn generate_jump(&mut self, line: usize) {
+        let idx = self
+            .lines
+            .iter()
+            .position(|&stmt_line| stmt_line >= line)
+            .unwrap_or(self.lines.len());
+        self.output.push_str(&format!("    i32.const {}\n", idx));
+        self.output.push_str("    local.set $pc\n");
+        self.output.push_str("    br $dispatch\n");
+    }
+
+    fn generate_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Assign { var_index, value } => {
+                self.generate_expr(value);
+                self.output.push_str(&format!("    local.set $var_{}\n", var_index));
+            },
+            Statement::Input { var_index } => {
+                self.generate_read_int();
+                self.output.push_str(&format!("    local.set $var_{}\n", var_index));
+            },
+            Statement::InputMany { var_index, count } => {
+                for var_index in *var_index..var_index + count {
+                    self.generate_statement(&Statement::Input { var_index });
+                }
+            },
+            Statement::InputChar { var_index } => {
+                self.output.push_str("    call $readbyte\n");
+                self.output.push_str(&format!("    local.set $var_{}\n", var_index));
+            },
+            Statement::PrintNum(expr) => {
+                self.generate_expr(expr);
+                self.output.push_str("    call $print\n");
+            },
+            Statement::PrintNumRadix { expr, radix } => self.generate_radix_print(expr, *radix),
+            Statement::PrintChar(expr) => {
+                self.generate_expr(expr);
+                self.output.push_str("    call $writebyte\n");
+            },
+            Statement::PrintNewline => {
+                self.output.push_str("    i64.const 10\n");
+                self.output.push_str("    call $writebyte\n");
+            },
+            Statement::PrintStr(text) => {
+                for byte in text.bytes() {
+                    self.output.push_str(&format!("    i64.const {}\n", byte));
+                    self.output.push_str("    call $writebyte\n");
+                }
+            },
+            Statement::Conditional {
+                condition,
+                body,
+                else_body,
+            } => {
+                // Body runs when the condition is zero
+                self.generate_expr(condition);
+                self.output.push_str("    i64.eqz\n");
+                self.output.push_str("    if\n");
+                for s in body {
+                    self.generate_statement(s);
+                }
+                if !else_body.is_empty() {
+                    self.output.push_str("    else\n");
+                    for s in else_body {
+                        self.generate_statement(s);
+                    }
+                }
+                self.output.push_str("    end\n");
+            },
+            Statement::While { .. } => unreachable!("loops are lowered before codegen"),
+            Statement::Goto { target, .. } => {
+                self.generate_jump(*target);
+            },
+            Statement::GotoDynamic(expr) => {
+                // No computed branch over source lines, so test each line in turn;
+                // unknown targets end the program like the Lamina backend
+                self.generate_expr(expr);
+                self.output.push_str("    local.set $target\n");
+                for line in 1..=self.max_line {
+                    self.output.push_str("    local.get $target\n");
+                    self.output.push_str(&format!("    i64.const {}\n", line));
+                    self.output.push_str("    i64.eq\n");
+                    self.output.push_str("    if\n");
+                    self.generate_jump(line);
+                    self.output.push_str("    end\n");
+                }
+                self.output.push_str("    i64.const 0\n");
+                self.output.push_str("    return\n");
+            },
+            Statement::Return(expr) => {
+                self.generate_expr(expr);
+                self.output.push_str("    return\n");
+            },
+            Statement::Eval(expr) => {
+                self.generate_expr(expr);
+                self.output.push_str("    drop\n");
+            },
+        }
+    }
+
+    fn generate_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Number(n) => self.output.push_str(&format!("    i64.const {}\n", n)),
+            Expr::Var(index) => self.output.push_str(&format!("    local.get $var_{}\n", index)),
+            Expr::InputNum => self.generate_read_int(),
+            Expr::Add(left, right) => self.generate_binary(left, right, "i64.add"),
+            Expr::Sub(left, right) => self.generate_binary(left, right, "i64.sub"),
+            Expr::Mul(left, right) => self.generate_binary(left, right, "i64.mul"),
+            Expr::Mod(left, right) => self.generate_binary(left, right, "i64.rem_s"),
+            Expr::Eq(left, right) => self.generate_comparison(left, right, "i64.eq"),
+            Expr::Lt(left, right) => self.generate_comparison(left, right, "i64.lt_s"),
+            Expr::Gt(left, right) => self.generate_comparison(left, right, "i64.gt_s"),
+            Expr::Neg(operand) => {
+                self.output.push_str("    i64.const 0\n");
+                self.generate_expr(operand);
+                self.output.push_str("    i64.sub\n");
+            },
+            Expr::Pow(base, exp) => {
+                // Evaluate the base once, then multiply it in exp times
+                let local = format!("$pow_{}", self.pow_locals);
+                self.pow_locals += 1;
+                self.generate_expr(base);
+                self.output.push_str(&format!("    local.set {}\n", local));
+                self.output.push_str("    i64.const 1\n");
+                for _ in 0..*exp {
+                    self.output.push_str(&format!("    local.get {}\n", local));
+                    self.output.push_str("    i64.mul\n");
+                }
+            },
+        }
+    }
+
+    // Same reader as the Lamina backend: skip spaces/newlines, then accumulate
+    // decimal digits until the first non-digit byte, leaving the value on the stack
+    fn generate_read_int(&mut self) {
+        let id = self.block_counter;
+        self.block_counter += 1;
+        self.output.push_str("    i64.const 0\n");
+        self.output.push_str("    local.set $acc\n");
+        self.output.push_str(&format!("    loop $skip_ws_{}\n", id));
+        self.output.push_str("    call $readbyte\n");
+        self.output.push_str("    local.tee $byte\n");
+        self.output.push_str("    i64.const 32\n");
+        self.output.push_str("    i64.eq\n");
+        self.output.push_str("    local.get $byte\n");
+        self.output.push_str("    i64.const 10\n");
+        self.output.push_str("    i64.eq\n");
+        self.output.push_str("    i32.or\n");
+        self.output.push_str(&format!("    br_if $skip_ws_{}\n", id));
+        self.output.push_str("    end\n");
+        self.output.push_str(&format!("    block $input_done_{}\n", id));
+        self.output.push_str(&format!("    loop $input_loop_{}\n", id));
+        self.output.push_str("    local.get $byte\n");
+        self.output.push_str("    i64.const 48\n");
+        self.output.push_str("    i64.lt_s\n");
+        self.output.push_str("    local.get $byte\n");
+        self.output.push_str("    i64.const 57\n");
+        self.output.push_str("    i64.gt_s\n");
+        self.output.push_str("    i32.or\n");
+        self.output.push_str(&format!("    br_if $input_done_{}\n", id));
+        self.output.push_str("    local.get $acc\n");
+        self.output.push_str("    i64.const 10\n");
+        self.output.push_str("    i64.mul\n");
+        self.output.push_str("    local.get $byte\n");
+        self.output.push_str("    i64.const 48\n");
+        self.output.push_str("    i64.sub\n");
+        self.output.push_str("    i64.add\n");
+        self.output.push_str("    local.set $acc\n");
+        self.output.push_str("    call $readbyte\n");
+        self.output.push_str("    local.set $byte\n");
+        self.output.push_str(&format!("    br $input_loop_{}\n", id));
+        self.output.push_str("    end\n");
+        self.output.push_str("    end\n");
+        self.output.push_str("    local.get $acc\n");
+    }
+
+    fn generate_binary(&mut self, left: &Expr, right: &Expr, op: &str) {
+        self.generate_expr(left);
+        self.generate_expr(right);
+        self.output.push_str(&format!("    {}\n", op));
+    }
+
+    // Comparisons yield an i32 flag, widened back to the i64 value type
+    fn generate_comparison(&mut self, left: &Expr, right: &Expr, op: &str) {
+        self.generate_binary(left, right, op);
+        self.output.push_str("    i64.extend_i32_u\n");
+    }
+}
@@ -0,0 +1,22 @@
+//! `# = <expr>` comments on expression temps, only with source comments on.
+
+use umjunsik::codegen::CodeGenerator;
+use umjunsik::{compile_umjunsik, ir_to_assembly, parse};
+
+const SOURCE: &str = "어떻게\n엄식?\n어엄어 어.\n식어어!\n이 사람이름이냐ㅋㅋ";
+
+#[test]
+fn annotated_temps_name_their_expression() {
+    let program = parse(SOURCE).unwrap();
+    let ir = CodeGenerator::builder().source(SOURCE).build().generate(&program).unwrap();
+    let annotated: Vec<&str> = ir.lines().filter_map(|line| line.split_once(" # = ")).map(|(_, expr)| expr).collect();
+    // At the default -O0 var 2 lives in a stack slot, so its load is a temp too
+    assert_eq!(annotated, ["input()", "var1 * (var1 + 1)", "var2"]);
+    // Lamina reads the trailing comments as comments
+    ir_to_assembly(&ir).unwrap();
+}
+
+#[test]
+fn default_ir_has_no_annotations() {
+    assert!(!compile_umjunsik(SOURCE).unwrap().contains(" # = "));
+}
@@ -0,0 +1,22 @@
+//! Smoke test for assembling through the library rather than the binary.
+
+use umjunsik::{Target, compile_umjunsik, host_target, ir_to_assembly, ir_to_assembly_for};
+
+#[test]
+fn trivial_program_assembles() {
+    let ir = compile_umjunsik("어떻게\n식.!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let assembly = ir_to_assembly(&ir).unwrap_or_else(|err| panic!("failed to assemble: {}", err));
+    assert!(!assembly.is_empty());
+}
+
+#[test]
+fn assembles_for_a_named_target() {
+    let ir = compile_umjunsik("어떻게\n식.!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let host = host_target().unwrap();
+    assert_eq!(ir_to_assembly_for(&ir, host).unwrap(), ir_to_assembly(&ir).unwrap());
+    // Every target assembles, whatever the host
+    for target in Target::ALL {
+        let assembly = ir_to_assembly_for(&ir, target).unwrap_or_else(|err| panic!("{}: {}", target, err));
+        assert!(!assembly.is_empty(), "{}", target);
+    }
+}
@@ -0,0 +1,25 @@
+//! Assignments, including a bare `엄` that clears its variable to 0.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::parse;
+
+#[test]
+fn bare_assignment_clears_wherever_the_statement_ends() {
+    // At the end of a line, before `~`, at the end of an else part, and before `:`
+    let source = "어떻게\n어엄.....\n어엄~식어어!\n동탄.?식.!:어엄\n동탄어어?어엄:식.!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        parse(source).unwrap().to_string(),
+        "   2: var2 = 5\n   3: var2 = 0\n   3: print_num(var2)\n   4: if 1 == 0 { print_num(1) } else { var2 = 0 }\n   \
+         5: if var2 == 0 { var2 = 0 } else { print_num(1) }\n"
+    );
+}
+
+#[test]
+fn clearing_stores_zero_into_the_existing_slot() {
+    let program = parse("어떻게\n어엄.....\n식어어!\n어엄\n식어어!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let ir = CodeGenerator::builder().opt_level(OptLevel::O0).build().generate(&program).unwrap();
+    assert!(ir.contains("# %var_ptr_0: var2\n"), "{}", ir);
+    assert_eq!(ir.matches("alloc.ptr").count(), 1, "{}", ir);
+    let line_4 = ir.split("line_4:").nth(1).unwrap();
+    assert_eq!(line_4.lines().nth(1).map(str::trim), Some("store.i64 %var_ptr_0, 0"), "{}", ir);
+}
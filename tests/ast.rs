@@ -0,0 +1,31 @@
+//! Helpers on a parsed `Program`.
+
+use std::collections::HashMap;
+use umjunsik::parse;
+
+#[test]
+fn line_map_skips_gaps_and_points_at_the_first_statement() {
+    // Statements on lines 2, 5 (two, joined with ~) and 8
+    let program = parse("어떻게\n식.!\n\n\n식..!~식...!\n\n\n식....!\n이 사람이름이냐ㅋㅋ").unwrap();
+    assert_eq!(program.line_map(), HashMap::from([(2, 0), (5, 1), (8, 3)]));
+}
+
+#[test]
+fn spans_point_at_each_statement_first_token() {
+    // A leading tab, a ~ segment, and a conditional
+    let program = parse("어떻게\n\t식.!~엄..\n동탄어?식.!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let spans: Vec<(usize, usize)> = (0..program.statements.len())
+        .map(|idx| program.span(idx))
+        .map(|span| (span.line, span.col))
+        .collect();
+    assert_eq!(spans, [(2, 2), (2, 6), (3, 1)]);
+    let lines: Vec<usize> = (0..program.statements.len()).map(|idx| program.line(idx)).collect();
+    assert_eq!(lines, [2, 2, 3]);
+}
+
+#[test]
+fn span_falls_back_to_column_one_without_spans() {
+    let mut program = parse("어떻게\n\t식.!\n이 사람이름이냐ㅋㅋ").unwrap();
+    program.spans.clear();
+    assert_eq!((program.span(0).line, program.span(0).col), (2, 1));
+}
@@ -0,0 +1,100 @@
+//! `CodeGenerator::builder()` options, alone and combined.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::{ir_to_assembly, parse};
+
+// Squares var 1 and prints it
+const SQUARE: &str = "어떻게\n엄...\n식어 어!\n이 사람이름이냐ㅋㅋ";
+
+// Lines whose opcode, after any `%t = `, starts with `instruction`
+fn instructions(ir: &str, instruction: &str) -> usize {
+    let opcode = |line: &str| line.trim().rsplit(" = ").next().unwrap_or("").to_string();
+    ir.lines().filter(|line| opcode(line).starts_with(instruction)).count()
+}
+
+#[test]
+fn two_options_both_apply() {
+    let program = parse(SQUARE).unwrap();
+    let ir = CodeGenerator::builder()
+        .checked(true)
+        .portable_print(true)
+        .opt_level(OptLevel::O0)
+        .build()
+        .generate(&program)
+        .unwrap();
+    assert!(ir.contains("overflow_trap"), "{}", ir);
+    assert_eq!(instructions(&ir, "print "), 0, "{}", ir);
+    assert!(instructions(&ir, "writebyte ") > 0, "{}", ir);
+}
+
+#[test]
+fn builder_defaults_match_new() {
+    let program = parse(SQUARE).unwrap();
+    let built = CodeGenerator::builder().build().generate(&program).unwrap();
+    assert_eq!(built, CodeGenerator::new(false).generate(&program).unwrap());
+    let checked = CodeGenerator::builder().checked(true).build().generate(&program).unwrap();
+    assert_eq!(checked, CodeGenerator::new(true).generate(&program).unwrap());
+}
+
+#[test]
+fn line_counter_only_with_max_lines() {
+    let program = parse("어떻게\n준..\n이 사람이름이냐ㅋㅋ").unwrap();
+    let plain = CodeGenerator::builder().build().generate(&program).unwrap();
+    assert!(!plain.contains("steps") && !plain.contains("line_limit_trap"), "{}", plain);
+
+    let limited = CodeGenerator::builder().max_lines(Some(100)).build().generate(&program).unwrap();
+    assert!(limited.contains("%steps_ptr = alloc.ptr.heap i64\n"), "{}", limited);
+    assert!(limited.contains("line_limit_trap:\n"), "{}", limited);
+}
+
+#[test]
+fn metadata_adds_only_a_header() {
+    // Var 1 lives in a stack slot at O0 and in SSA temps at O2
+    for (opt_level, header) in [
+        (OptLevel::O0, "# metadata: stack_slots=1 ssa_vars=0 statements=2 lines=3\n@noinline\n"),
+        (OptLevel::O2, "# metadata: stack_slots=0 ssa_vars=1 statements=2 lines=3\n@noinline\n"),
+    ] {
+        let program = parse(SQUARE).unwrap();
+        let generate = |with_metadata| {
+            CodeGenerator::builder()
+                .opt_level(opt_level)
+                .with_metadata(with_metadata)
+                .build()
+                .generate(&program)
+                .unwrap()
+        };
+        let (plain, annotated) = (generate(false), generate(true));
+        assert!(!plain.contains("metadata") && !plain.contains("@noinline"), "{}", plain);
+        assert_eq!(annotated.replacen(header, "", 1), plain, "{}", annotated);
+        // Lamina still assembles the annotated function
+        assert!(ir_to_assembly(&annotated).is_ok(), "{}", annotated);
+    }
+}
+
+#[test]
+fn source_comments_label_each_line() {
+    let source = "어떻게\n엄...\n\n\t식어 어!  # square\n이 사람이름이냐ㅋㅋ";
+    let program = parse(source).unwrap();
+    let annotated = CodeGenerator::builder().source(source).build().generate(&program).unwrap();
+    let lines: Vec<&str> = annotated.lines().map(str::trim).collect();
+    // Each comment sits right above the label of the line it quotes; the empty line 3 gets none
+    let comments: Vec<(&str, &str)> = lines
+        .windows(2)
+        .filter(|pair| pair[0].starts_with("# source line"))
+        .map(|pair| (pair[0], pair[1]))
+        .collect();
+    assert_eq!(
+        comments,
+        [("# source line 2, col 1: 엄...", "line_2:"), ("# source line 4, col 2: 식어 어!  # square", "line_4:")]
+    );
+
+    // Without a source there are no source comments, and otherwise the IR is the same
+    let plain = CodeGenerator::builder().build().generate(&program).unwrap();
+    assert!(!plain.contains("# source line") && !plain.contains(" # = "), "{}", plain);
+    let stripped: Vec<&str> = annotated
+        .lines()
+        .filter(|line| !line.trim().starts_with("# source line"))
+        .map(|line| line.split(" # = ").next().unwrap())
+        .collect();
+    assert_eq!(stripped, plain.lines().collect::<Vec<_>>());
+}
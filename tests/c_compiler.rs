@@ -0,0 +1,57 @@
+//! Order in which `select_c_compiler` picks the compiler used by `--run`.
+
+use std::cell::RefCell;
+use umjunsik::{C_COMPILER_CANDIDATES, select_c_compiler};
+
+#[test]
+fn explicit_option_wins() {
+    assert_eq!(select_c_compiler(Some("/opt/cc"), Some("gcc"), |_| true), Some("/opt/cc".to_string()));
+}
+
+#[test]
+fn cc_variable_before_probe() {
+    assert_eq!(select_c_compiler(None, Some("tcc"), |_| true), Some("tcc".to_string()));
+    // An empty CC counts as unset
+    assert_eq!(select_c_compiler(None, Some(""), |_| true), Some("clang".to_string()));
+}
+
+#[test]
+fn probe_order() {
+    assert_eq!(select_c_compiler(None, None, |cc| cc != "clang"), Some("cc".to_string()));
+    assert_eq!(select_c_compiler(None, None, |cc| cc == "gcc"), Some("gcc".to_string()));
+}
+
+#[test]
+fn probe_stops_at_the_first_present_candidate() {
+    // Every subset of the candidates, as a bitmask over C_COMPILER_CANDIDATES
+    for mask in 0..1 << C_COMPILER_CANDIDATES.len() {
+        let present = |idx: usize| mask & (1 << idx) != 0;
+        let probed = RefCell::new(Vec::new());
+        let picked = select_c_compiler(None, None, |cc| {
+            probed.borrow_mut().push(cc.to_string());
+            present(C_COMPILER_CANDIDATES.iter().position(|candidate| *candidate == cc).unwrap())
+        });
+        let first = (0..C_COMPILER_CANDIDATES.len()).find(|&idx| present(idx));
+        assert_eq!(picked.as_deref(), first.map(|idx| C_COMPILER_CANDIDATES[idx]), "mask {:b}", mask);
+        // Candidates are probed in order, and none after the one picked
+        let probed_until = first.map_or(C_COMPILER_CANDIDATES.len(), |idx| idx + 1);
+        assert_eq!(probed.into_inner(), C_COMPILER_CANDIDATES[..probed_until], "mask {:b}", mask);
+    }
+}
+
+#[test]
+fn explicit_or_cc_variable_skips_the_probe() {
+    let probed = RefCell::new(0);
+    let probe = |_: &str| {
+        *probed.borrow_mut() += 1;
+        true
+    };
+    select_c_compiler(Some("/opt/cc"), None, probe);
+    select_c_compiler(None, Some("tcc"), probe);
+    assert_eq!(probed.into_inner(), 0);
+}
+
+#[test]
+fn nothing_found() {
+    assert_eq!(select_c_compiler(None, None, |_| false), None);
+}
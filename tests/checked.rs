@@ -0,0 +1,65 @@
+//! `--checked`: i64 overflow traps instead of wrapping, in the generated code
+//! and in the interpreter alike.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::{InterpretOptions, interpret, ir_to_assembly, parse};
+
+// Squares var 1 in a goto loop; the sixth square is past i64::MAX
+const SQUARES: &str = "어떻게\n엄...\n엄어 어\n식어!\n준...\n이 사람이름이냐ㅋㅋ";
+
+// Reads two numbers and prints their product and a difference, all in range
+const IN_RANGE: &str = "어떻게\n엄식?\n어엄식?\n식어 어어 어어,!\n식-어^...!\n이 사람이름이냐ㅋㅋ";
+
+fn run_checked(source: &str, input: &str) -> (String, i64) {
+    let program = parse(source).unwrap();
+    let mut stdout = Vec::new();
+    let status = InterpretOptions::default()
+        .checked(true)
+        .interpret(&program, &mut input.as_bytes(), &mut stdout)
+        .unwrap();
+    (String::from_utf8(stdout).unwrap(), status)
+}
+
+#[test]
+fn interpreter_traps_on_overflow() {
+    assert_eq!(
+        run_checked(SQUARES, ""),
+        (
+            "9\n81\n6561\n43046721\n1853020188851841\narithmetic overflow\n".to_string(),
+            1
+        )
+    );
+    for source in [
+        "어떻게\n엄@9223372036854775807\n식어.!\n이 사람이름이냐ㅋㅋ",
+        "어떻게\n엄-@9223372036854775807,\n식-어!\n이 사람이름이냐ㅋㅋ",
+        "어떻게\n엄@3037000500\n식어^..!\n이 사람이름이냐ㅋㅋ",
+        "어떻게\n엄-@9223372036854775807,\n어엄,\n식어%어어!\n이 사람이름이냐ㅋㅋ",
+    ] {
+        assert_eq!(run_checked(source, ""), ("arithmetic overflow\n".to_string(), 1), "{}", source);
+    }
+}
+
+#[test]
+fn interpreter_runs_in_range_arithmetic_unchanged() {
+    assert_eq!(run_checked(IN_RANGE, "3 4"), ("36\n-27\n".to_string(), 0));
+}
+
+#[test]
+fn interpreter_wraps_without_checked() {
+    let program = parse("어떻게\n엄@9223372036854775807\n식어.!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let mut stdout = Vec::new();
+    assert_eq!(interpret(&program, &mut "".as_bytes(), &mut stdout), Ok(0));
+    assert_eq!(String::from_utf8(stdout).unwrap(), "-9223372036854775808\n");
+}
+
+#[test]
+fn checked_code_assembles_at_every_level() {
+    for source in [SQUARES, IN_RANGE] {
+        let program = parse(source).unwrap();
+        for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+            let ir = CodeGenerator::builder().checked(true).opt_level(level).build().generate(&program).unwrap();
+            assert!(ir.contains("overflow_trap:"), "{}", ir);
+            ir_to_assembly(&ir).unwrap_or_else(|err| panic!("{:?} failed to assemble: {}", level, err));
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Flag combinations on the command line: stats with codegen options,
+//! several input files with the flags a single file takes, and validation
+//! ahead of every backend.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn umjunsik(args: &[&str], files: &[&PathBuf]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_umjunsik")).args(args).args(files).output().unwrap()
+}
+
+// Writes `source` to a temp file named after the calling test
+fn temp_source(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("umjunsik-cli-{}-{}.umm", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn stats_follow_codegen_flags() {
+    let path = temp_source("stats", "어떻게\n엄...\n식어 어!\n이 사람이름이냐ㅋㅋ");
+    let plain = umjunsik(&["--quiet", "--stats"], &[&path]);
+    let checked = umjunsik(&["--quiet", "--stats", "--checked", "-O0", "--portable-print"], &[&path]);
+    fs::remove_file(path).unwrap();
+
+    assert!(plain.status.success(), "{}", String::from_utf8_lossy(&plain.stderr));
+    assert!(checked.status.success(), "{}", String::from_utf8_lossy(&checked.stderr));
+    assert!(String::from_utf8_lossy(&checked.stderr).contains("Compile stats"));
+    let (plain, checked) = (String::from_utf8(plain.stdout).unwrap(), String::from_utf8(checked.stdout).unwrap());
+    assert!(!plain.contains("overflow_trap"), "{}", plain);
+    assert!(checked.contains("overflow_trap"), "{}", checked);
+}
+
+#[test]
+fn several_files_take_codegen_and_interpreter_flags() {
+    let first = temp_source("multi1", "어떻게\n엄@9223372036854775807\n이 사람이름이냐ㅋㅋ\n");
+    let second = temp_source("multi2", "어떻게\n식어.!\n이 사람이름이냐ㅋㅋ");
+    let wrapped = umjunsik(&["--quiet", "--interpret"], &[&first, &second]);
+    let trapped = umjunsik(&["--quiet", "--interpret", "--checked"], &[&first, &second]);
+    let compiled = umjunsik(&["--quiet", "--checked", "-O0", "--emit", "ir"], &[&first, &second]);
+    let ast = umjunsik(&["--quiet", "--emit", "ast"], &[&first, &second]);
+    let tokens = umjunsik(&["--quiet", "--emit", "tokens"], &[&first, &second]);
+    fs::remove_file(first).unwrap();
+    fs::remove_file(second).unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&wrapped.stdout), "-9223372036854775808\n");
+    assert_eq!(String::from_utf8_lossy(&trapped.stdout), "arithmetic overflow\n");
+    assert_eq!(trapped.status.code(), Some(1));
+    assert!(compiled.status.success(), "{}", String::from_utf8_lossy(&compiled.stderr));
+    assert!(String::from_utf8_lossy(&compiled.stdout).contains("overflow_trap"));
+    assert_eq!(
+        String::from_utf8_lossy(&ast.stdout),
+        "   2: var1 = 9223372036854775807\n   5: print_num(var1 + 1)\n"
+    );
+    // Token positions are per file, so there is no merged token stream
+    assert!(!tokens.status.success());
+}
+
+#[test]
+fn merged_errors_point_into_files_with_any_line_endings() {
+    let first = temp_source("cr", "어떻게\r식.!\r이 사람이름이냐ㅋㅋ\r");
+    let second = temp_source("lf", "어떻게\n식!\n이 사람이름이냐ㅋㅋ");
+    let output = umjunsik(&[], &[&first, &second]);
+    fs::remove_file(first).unwrap();
+    fs::remove_file(second).unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("5 | 식!\n"), "{}", stderr);
+}
+
+#[test]
+fn interpreter_rejects_what_compile_rejects() {
+    let path = temp_source("bad_goto", "어떻게\n식.!\n준.........\n이 사람이름이냐ㅋㅋ");
+    let outputs = [
+        umjunsik(&["--interpret"], &[&path]),
+        umjunsik(&[], &[&path]),
+        umjunsik(&["--backend", "c"], &[&path]),
+    ];
+    fs::remove_file(path).unwrap();
+
+    for output in outputs {
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty(), "{}", String::from_utf8_lossy(&output.stdout));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("goto target line 9 does not exist"), "{}", stderr);
+    }
+}
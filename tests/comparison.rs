@@ -0,0 +1,78 @@
+//! `=`, `<` and `>`: comparisons that give 1 or 0, as parsed, generated and run.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::interp::interpret;
+use umjunsik::parse;
+
+fn compile_at(source: &str, opt_level: OptLevel) -> String {
+    let program = parse(source).unwrap_or_else(|err| panic!("failed to parse: {}", err));
+    CodeGenerator::builder().opt_level(opt_level).build().generate(&program).unwrap()
+}
+
+// The instructions of line 4, which compares the two numbers read on lines 2 and 3
+fn line_4(op: &str) -> Vec<String> {
+    let source = format!("어떻게\n엄식?\n어엄식?\n식어{}어어!\n이 사람이름이냐ㅋㅋ", op);
+    let ir = compile_at(&source, OptLevel::O0);
+    let block = ir.split("line_4:").nth(1).unwrap();
+    block.lines().skip(1).map(str::trim).take_while(|line| !line.is_empty()).map(String::from).collect()
+}
+
+fn run(source: &str, input: &str) -> String {
+    let program = parse(source).unwrap_or_else(|err| panic!("{:?} failed to parse: {}", source, err));
+    let mut stdout = Vec::new();
+    interpret(&program, &mut input.as_bytes(), &mut stdout).unwrap();
+    String::from_utf8(stdout).unwrap()
+}
+
+fn assert_compares_with(op: &str, instruction: &str) {
+    let lines = line_4(op);
+    let flag = lines
+        .iter()
+        .find_map(|line| line.split_once(&format!(" = {} ", instruction)).map(|(flag, _)| flag.to_string()))
+        .unwrap_or_else(|| panic!("no {} in {:#?}", instruction, lines));
+    assert!(lines.iter().any(|line| line.ends_with(&format!("= zext.bool.i64 {}", flag))), "{:#?}", lines);
+}
+
+#[test]
+fn equals_lowers_to_eq() {
+    assert_compares_with("=", "eq.i64");
+}
+
+#[test]
+fn less_than_lowers_to_lt() {
+    assert_compares_with("<", "lt.i64");
+}
+
+#[test]
+fn greater_than_lowers_to_gt() {
+    assert_compares_with(">", "gt.i64");
+}
+
+#[test]
+fn comparisons_give_one_or_zero() {
+    let source = "어떻게\n엄식?\n어엄식?\n식어=어어!\n식어<어어!\n식어>어어!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(run(source, "3 4"), "0\n1\n0\n");
+    assert_eq!(run(source, "4 4"), "1\n0\n0\n");
+    assert_eq!(run(source, "5 4"), "0\n0\n1\n");
+}
+
+#[test]
+fn comparison_binds_looser_than_arithmetic() {
+    let program = parse("어떻게\n엄...\n식어<어 ..!\n이 사람이름이냐ㅋㅋ").unwrap();
+    assert_eq!(program.to_string(), "   2: var1 = 3\n   3: print_num(var1 < var1 * 2)\n");
+}
+
+#[test]
+fn true_comparison_skips_the_conditional_body() {
+    let source = "어떻게\n엄식?\n어엄식?\n동탄어<어어?식.!:식..!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(run(source, "3 4"), "2\n");
+    assert_eq!(run(source, "4 3"), "1\n");
+}
+
+#[test]
+fn constant_comparisons_fold() {
+    let ir = compile_at("어떻게\n식..<...!\n식..>...!\n이 사람이름이냐ㅋㅋ", OptLevel::O1);
+    for instruction in ["lt.i64", "gt.i64", "zext.bool.i64"] {
+        assert!(!ir.contains(instruction), "{}", ir);
+    }
+}
@@ -0,0 +1,50 @@
+//! The library's file and reader entry points, which compile like `compile_umjunsik`.
+
+use std::fs;
+use umjunsik::{
+    CompileError, compile_umjunsik, compile_umjunsik_file, compile_umjunsik_reader, compile_umjunsik_with_stats,
+};
+
+const HELLO: &str = "어떻게\n엄...\n식어!\n이 사람이름이냐ㅋㅋ";
+
+#[test]
+fn file_compiles_like_its_source() {
+    let path = std::env::temp_dir().join(format!("umjunsik-compile-{}.umm", std::process::id()));
+    fs::write(&path, HELLO).unwrap();
+    let ir = compile_umjunsik_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(ir.unwrap(), compile_umjunsik(HELLO).unwrap());
+}
+
+#[test]
+fn reader_compiles_like_its_source() {
+    assert_eq!(compile_umjunsik_reader(HELLO.as_bytes()).unwrap(), compile_umjunsik(HELLO).unwrap());
+}
+
+#[test]
+fn read_failures_are_io_errors() {
+    let path = std::env::temp_dir().join("umjunsik-compile-missing.umm");
+    let err = compile_umjunsik_file(&path).unwrap_err();
+    assert!(matches!(err, CompileError::Io { .. }), "{:?}", err);
+    assert!(err.to_string().starts_with("Error reading file '"), "{}", err);
+    assert_eq!(err.location(), None);
+
+    let err = compile_umjunsik_reader(&[0xff, 0xfe][..]).unwrap_err();
+    assert!(matches!(err, CompileError::Io { .. }), "{:?}", err);
+    assert!(err.to_string().starts_with("Error reading source: "), "{}", err);
+}
+
+#[test]
+fn compile_errors_pass_through() {
+    let err = compile_umjunsik_reader("어떻게\n식!\n이 사람이름이냐ㅋㅋ".as_bytes()).unwrap_err();
+    assert_eq!((err.to_string(), err.location()), ("식! requires an expression to print".to_string(), Some((2, 2))));
+}
+
+#[test]
+fn stats_count_lines_with_any_line_ending() {
+    for source in [HELLO.to_string(), HELLO.replace('\n', "\r\n"), HELLO.replace('\n', "\r")] {
+        let (ir, stats) = compile_umjunsik_with_stats(&source).unwrap();
+        assert_eq!(stats.source_lines, 4, "{:?}", source);
+        assert_eq!(ir, compile_umjunsik(HELLO).unwrap());
+    }
+}
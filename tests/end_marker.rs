@@ -0,0 +1,60 @@
+//! A source that ends without `이 사람이름이냐ㅋㅋ`: a warning by default, an error when strict.
+
+use umjunsik::token::Token;
+use umjunsik::{Warning, lint, parse, parse_multi, parse_strict, tokenize};
+
+const UNTERMINATED: &str = "어떻게\n엄...\n식어!\n";
+
+#[test]
+fn missing_end_marker_warns_by_default() {
+    let program = parse(UNTERMINATED).unwrap();
+    assert_eq!(program.missing_end_marker, Some(3));
+    assert_eq!(lint(&program), [Warning::MissingEndMarker { line: 3 }]);
+    assert_eq!(
+        lint(&program)[0].to_string(),
+        "program missing 이 사람이름이냐ㅋㅋ end marker at line 3"
+    );
+}
+
+#[test]
+fn missing_end_marker_is_an_error_when_strict() {
+    let err = parse_strict(UNTERMINATED).unwrap_err();
+    assert_eq!(err.to_string(), "program missing 이 사람이름이냐ㅋㅋ end marker");
+    assert_eq!(err.location(), Some((4, 1)));
+    assert!(parse_strict("어떻게\n식.!\n이 사람이름이냐ㅋㅋ").is_ok());
+}
+
+#[test]
+fn merged_files_point_at_the_unterminated_one() {
+    let program = parse_multi(&[UNTERMINATED, "어떻게\n식.!\n이 사람이름이냐ㅋㅋ\n"]).unwrap();
+    assert_eq!(program.missing_end_marker, Some(3));
+}
+
+fn tokens(source: &str) -> Vec<Token> {
+    let tokens = tokenize(source).unwrap_or_else(|err| panic!("{:?} failed to lex: {}", source, err));
+    tokens.into_iter().map(|token| token.token).collect()
+}
+
+#[test]
+fn end_marker_takes_any_number_of_trailing_k() {
+    for marker in ["이 사람이름이냐", "이 사람이름이냐ㅋ", "이 사람이름이냐ㅋㅋ", "이 사람이름이냐ㅋㅋㅋㅋㅋㅋㅋ"] {
+        assert_eq!(tokens(marker), [Token::IEotteonSaram, Token::EOF], "{}", marker);
+        assert!(parse_strict(&format!("어떻게\n식.!\n{}", marker)).is_ok(), "{}", marker);
+    }
+}
+
+#[test]
+fn end_marker_tolerates_spacing_after_i() {
+    for marker in ["이사람이름이냐ㅋㅋ", "이   사람이름이냐ㅋㅋ", "이\t 사람이름이냐ㅋㅋ"] {
+        assert_eq!(tokens(marker), [Token::IEotteonSaram, Token::EOF], "{:?}", marker);
+    }
+}
+
+#[test]
+fn near_misses_are_not_the_end_marker() {
+    // Longer words that merely contain 사람이름이냐 are unknown, not the end
+    for word in ["이사람이름이냐고", "이 사람이름이냐ㅋㅋ요", "이사람", "이 사람이름", "이름이냐"] {
+        let err = tokenize(word).expect_err(word);
+        assert!(err.to_string().starts_with("Unknown keyword"), "{}: {}", word, err);
+    }
+}
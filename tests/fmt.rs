@@ -0,0 +1,30 @@
+//! `format_source`, behind `--fmt`: canonical spacing, one statement per line.
+
+use std::path::PathBuf;
+use umjunsik::format_source;
+
+#[test]
+fn fixes_irregular_spacing() {
+    let source = "어떻게\n엄...\n식어 어 ..  !~식ㅋ\n\t어엄어 어 # square\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        format_source(source).unwrap(),
+        "어떻게\n엄...\n식어 어 ..!\n식ㅋ\n어엄어 어 # square\n이 사람이름이냐ㅋㅋ\n"
+    );
+}
+
+#[test]
+fn formatting_formatted_source_is_a_no_op() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    for sub in ["examples", "tests/fixtures"] {
+        for entry in std::fs::read_dir(dir.join(sub)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "umm") {
+                continue;
+            }
+            let Ok(once) = format_source(&std::fs::read_to_string(&path).unwrap()) else {
+                continue;
+            };
+            assert_eq!(format_source(&once).unwrap(), once, "{}", path.display());
+        }
+    }
+}
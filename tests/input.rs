@@ -0,0 +1,92 @@
+//! The generated stdin readers: integers with `식?`, raw bytes with `식ㅋ?`.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::parse;
+
+fn compile_at(source: &str, opt_level: OptLevel) -> String {
+    let program = parse(source).unwrap_or_else(|err| panic!("failed to parse: {}", err));
+    CodeGenerator::builder().opt_level(opt_level).build().generate(&program).unwrap()
+}
+
+fn count(ir: &str, instruction: &str) -> usize {
+    ir.lines().filter(|line| line.contains(&format!(" = {} ", instruction))).count()
+}
+
+#[test]
+fn integer_reader_checks_digits_with_a_range() {
+    // The reader used to test each of the ten digits with its own eq.i64
+    let ir = compile_at("어떻게\n엄식?\n이 사람이름이냐ㅋㅋ", OptLevel::O0);
+    assert!(ir.lines().count() < 70, "{} lines:\n{}", ir.lines().count(), ir);
+    // Only the whitespace test compares for equality
+    assert_eq!(count(&ir, "eq.i64"), 2, "{}", ir);
+    assert_eq!(count(&ir, "ge.i64"), 1, "{}", ir);
+    assert_eq!(count(&ir, "le.i64"), 1, "{}", ir);
+}
+
+#[test]
+fn byte_input_is_one_readbyte_into_its_variable() {
+    let ir = compile_at("어떻게\n엄.\n어엄식ㅋ?\n식어어ㅋ\n이 사람이름이냐ㅋㅋ", OptLevel::O0);
+    assert!(ir.contains("# %var_ptr_1: var2\n"), "{}", ir);
+    let line_3: Vec<&str> = ir.split("line_3:").nth(1).unwrap().lines().map(str::trim).collect();
+    assert_eq!(line_3[1..3], ["%t0 = readbyte", "store.i64 %var_ptr_1, %t0"]);
+    assert_eq!(ir.matches("readbyte").count(), 1, "{}", ir);
+}
+
+#[test]
+fn whitespace_branch_tests_a_genuine_bool() {
+    let ir = compile_at("어떻게\n엄식?\n이 사람이름이냐ㅋㅋ", OptLevel::O0);
+    let block: Vec<&str> = ir
+        .split("input_skip_ws_0:")
+        .nth(1)
+        .unwrap()
+        .lines()
+        .map(str::trim)
+        .take_while(|line| !line.ends_with(':'))
+        .collect();
+    let defined = |var: &str| -> &str {
+        block.iter().find_map(|line| line.strip_prefix(&format!("{} = ", var))).unwrap_or_else(|| panic!("{}", var))
+    };
+    let operands = |instr: &str, op: &str| -> Vec<String> {
+        let args = instr.strip_prefix(op).unwrap_or_else(|| panic!("{} is not {}", instr, op));
+        args.split(", ").map(|arg| arg.trim().to_string()).collect()
+    };
+
+    // br on (space + newline) > 0, each flag a zero-extended equality test, so
+    // even if both tests held the branch would see 1 rather than 2
+    let condition = block.iter().find_map(|line| line.strip_prefix("br ")).unwrap().split(", ").next().unwrap();
+    let compared = operands(defined(condition), "gt.i64 ");
+    assert_eq!(compared[1], "0");
+    for flag in operands(defined(&compared[0]), "add.i64 ") {
+        let test = defined(defined(&flag).strip_prefix("zext.bool.i64 ").unwrap());
+        assert!(test.starts_with("eq.i64 "), "{}", test);
+    }
+}
+
+#[test]
+fn input_count_fills_consecutive_variables() {
+    let source = "어떻게\n어엄식?...\n식어어!\n식어어어!\n식어어어어!\n이 사람이름이냐ㅋㅋ";
+    let program = parse(source).unwrap();
+    assert_eq!(program.to_string().lines().next().unwrap(), "   2: var2..var4 = input()");
+    let mut stdout = Vec::new();
+    umjunsik::interp::interpret(&program, &mut "10 20 30".as_bytes(), &mut stdout).unwrap();
+    assert_eq!(String::from_utf8(stdout).unwrap(), "10\n20\n30\n");
+
+    // One reader per variable, each ending in a store to its own slot
+    let ir = compile_at(source, OptLevel::O0);
+    let readers = ir.lines().filter(|line| line.trim().starts_with("input_skip_ws_") && line.ends_with(':'));
+    assert_eq!(readers.count(), 3, "{}", ir);
+    let stores: Vec<&str> = ir
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("store.i64 %var_ptr_") && !line.ends_with(", 0"))
+        .collect();
+    assert_eq!(stores.len(), 3, "{}", ir);
+    for (slot, var) in [(0, 2), (1, 3), (2, 4)] {
+        assert!(ir.contains(&format!("# %var_ptr_{}: var{}\n", slot, var)), "{}", ir);
+        assert!(stores[slot].starts_with(&format!("store.i64 %var_ptr_{}, ", slot)), "{:?}", stores);
+    }
+    // An @ literal count, and a count past the variable limit
+    assert_eq!(parse("어떻게\n엄식?@2\n이 사람이름이냐ㅋㅋ").unwrap().to_string(), "   2: var1..var2 = input()\n");
+    assert!(parse("어떻게\n엄식?@10000\n이 사람이름이냐ㅋㅋ").is_ok());
+    assert!(parse("어떻게\n엄식?@10001\n이 사람이름이냐ㅋㅋ").is_err());
+}
@@ -0,0 +1,42 @@
+//! `InterpretOptions` that run a program like code generated with the
+//! matching `CodeGeneratorBuilder` options.
+
+use umjunsik::{InterpretOptions, parse};
+
+// Counts down from 3 with a goto loop and returns 2: ten statements run in all
+const COUNTDOWN: &str = "어떻게\n엄...\n식어!~엄어,\n동탄어?화이팅!..:준...\n이 사람이름이냐ㅋㅋ";
+
+fn run(options: InterpretOptions, source: &str) -> (String, i64) {
+    let program = parse(source).unwrap_or_else(|err| panic!("{:?} failed to parse: {}", source, err));
+    let mut stdout = Vec::new();
+    let status = options.interpret(&program, &mut "".as_bytes(), &mut stdout).unwrap();
+    (String::from_utf8(stdout).unwrap(), status)
+}
+
+#[test]
+fn truthy_conditionals_run_the_body_on_nonzero() {
+    let source = "어떻게\n동탄.?식.!:식..!\n동탄.,?식.!:식..!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(run(InterpretOptions::default(), source), ("2\n1\n".to_string(), 0));
+    let truthy = InterpretOptions::default().truthy_conditionals(true);
+    assert_eq!(run(truthy, source), ("1\n2\n".to_string(), 0));
+}
+
+#[test]
+fn max_lines_traps_past_the_limit() {
+    let limited = |max_lines| run(InterpretOptions::default().max_lines(Some(max_lines)), COUNTDOWN);
+    assert_eq!(run(InterpretOptions::default(), COUNTDOWN), ("3\n2\n1\n".to_string(), 2));
+    // The last conditional, whose body returns, is the tenth statement
+    assert_eq!(limited(10), ("3\n2\n1\n".to_string(), 2));
+    assert_eq!(limited(9), ("3\n2\n1\nline limit exceeded\n".to_string(), 1));
+    // The fifth statement is the second print
+    assert_eq!(limited(4), ("3\nline limit exceeded\n".to_string(), 1));
+}
+
+#[test]
+fn max_lines_stops_an_endless_goto_loop() {
+    let source = "어떻게\n식.!\n준..\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        run(InterpretOptions::default().max_lines(Some(5)), source),
+        ("1\n1\n1\nline limit exceeded\n".to_string(), 1)
+    );
+}
@@ -0,0 +1,94 @@
+//! Block labels: which empty lines get one, and that no two blocks share a name.
+
+use std::collections::BTreeSet;
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::parse;
+
+// Statements on lines 2 and 10 only, with a goto into the gap at line 5
+const SPARSE: &str = "어떻게\n동탄.?준.....\n\n\n\n\n\n\n\n식.!\n이 사람이름이냐ㅋㅋ";
+
+fn compile_at(source: &str, opt_level: OptLevel) -> String {
+    let program = parse(source).unwrap_or_else(|err| panic!("failed to parse: {}", err));
+    CodeGenerator::builder().opt_level(opt_level).build().generate(&program).unwrap()
+}
+
+fn labels(ir: &str) -> Vec<&str> {
+    ir.lines().filter_map(|line| line.trim().strip_suffix(':')).collect()
+}
+
+// Each empty line's label and the label its block jumps to
+fn forwards(ir: &str) -> Vec<(String, String)> {
+    let lines: Vec<&str> = ir.lines().map(str::trim).collect();
+    lines
+        .windows(2)
+        .filter_map(|pair| Some((pair[0].strip_suffix(':')?, pair[1].strip_prefix("jmp ")?)))
+        .filter(|(label, _)| label.starts_with("line_"))
+        .map(|(label, target)| (label.to_string(), target.to_string()))
+        .collect()
+}
+
+#[test]
+fn only_goto_targets_among_empty_lines_get_labels() {
+    let ir = compile_at(SPARSE, OptLevel::O1);
+    assert_eq!(labels(&ir), ["entry", "line_2", "then_0", "else_1", "line_5", "line_10"]);
+    assert_eq!(forwards(&ir), [("line_5".to_string(), "line_10".to_string())]);
+}
+
+#[test]
+fn empty_lines_forward_straight_to_the_next_statement() {
+    // Unoptimized, every empty line has a label, but none chains to the next empty line
+    let ir = compile_at(SPARSE, OptLevel::O0);
+    let forwards = forwards(&ir);
+    let expected: Vec<(String, String)> = [(1, 2), (3, 10), (4, 10), (5, 10), (6, 10), (7, 10), (8, 10), (9, 10)]
+        .iter()
+        .map(|(line, target)| (format!("line_{}", line), format!("line_{}", target)))
+        .collect();
+    assert_eq!(forwards, expected);
+}
+
+#[test]
+fn conditionals_and_input_never_repeat_a_label() {
+    let source = "어떻게\n엄식?\n동탄어?식.!:식..!\n동탄어,?식...!\n이 사람이름이냐ㅋㅋ";
+    for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+        let ir = compile_at(source, level);
+        let labels = labels(&ir);
+        let unique: BTreeSet<&str> = labels.iter().copied().collect();
+        assert_eq!(unique.len(), labels.len(), "{:?}:\n{}", level, ir);
+    }
+}
+
+#[test]
+fn goto_chain_collapses_to_one_jump() {
+    // Line 2 goes to 3, which goes to 4, which goes to 5
+    let source = "어떻게\n준...\n준....\n준.....\n식.!\n이 사람이름이냐ㅋㅋ";
+    let forwarded = |level| {
+        let ir = compile_at(source, level);
+        forwards(&ir).into_iter().find(|(label, _)| label == "line_2").unwrap().1
+    };
+    assert_eq!(forwarded(OptLevel::O2), "line_5");
+    assert_eq!(forwarded(OptLevel::O1), "line_3");
+}
+
+#[test]
+fn goto_cycle_compiles() {
+    // Lines 2 and 3 jump to each other; threading must give up rather than chase forever
+    let source = "어떻게\n준...\n준..\n이 사람이름이냐ㅋㅋ";
+    let ir = compile_at(source, OptLevel::O2);
+    assert_eq!(
+        forwards(&ir),
+        [("line_2".to_string(), "line_3".to_string()), ("line_3".to_string(), "line_2".to_string())]
+    );
+}
+
+#[test]
+fn else_if_chain_shares_one_continuation() {
+    // Prints 1, 2, 3 or 4 for input 0, 1, 2 or anything else
+    let source = "어떻게\n엄식?\n동탄어?식.!:동탄어,?식..!:동탄어,,?식...!:식....!\n식.....!\n이 사람이름이냐ㅋㅋ";
+    for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+        let ir = compile_at(source, level);
+        let endifs: Vec<&str> = labels(&ir).into_iter().filter(|label| label.starts_with("endif")).collect();
+        assert_eq!(endifs.len(), 1, "{:?}:\n{}", level, ir);
+        assert_eq!(ir.matches(&format!("jmp {}\n", endifs[0])).count(), 4, "{:?}:\n{}", level, ir);
+        assert_eq!(labels(&ir).iter().filter(|label| label.starts_with("then")).count(), 3);
+    }
+}
@@ -0,0 +1,220 @@
+//! Token streams and positions for the lexer's input forms.
+
+use umjunsik::ast::{Expr, Statement};
+use umjunsik::lexer::{Lexer, expand_ascii_aliases};
+use umjunsik::parser::Parser;
+use umjunsik::token::{Token, TokenWithPos};
+use umjunsik::{compile_umjunsik, tokenize, tokens_to_source};
+
+// Each token with its line and column, ending at `Token::EOF`
+fn positions(source: &str) -> Vec<(Token, usize, usize)> {
+    let tokens = tokenize(source).unwrap_or_else(|err| panic!("{:?} failed to lex: {}", source, err));
+    tokens.into_iter().map(|token| (token.token, token.line, token.col)).collect()
+}
+
+#[test]
+fn comment_after_a_statement() {
+    assert_eq!(
+        positions("식.! # print 1\n식..!"),
+        [
+            (Token::Sik, 1, 1),
+            (Token::Dot(1), 1, 2),
+            (Token::Exclamation, 1, 3),
+            (Token::Newline, 1, 14),
+            (Token::Sik, 2, 1),
+            (Token::Dot(2), 2, 2),
+            (Token::Exclamation, 2, 4),
+            (Token::EOF, 2, 5),
+        ]
+    );
+}
+
+#[test]
+fn comment_only_line() {
+    assert_eq!(
+        positions("# 어떻게 식.!\n엄."),
+        [
+            (Token::Newline, 1, 10),
+            (Token::Eom(0), 2, 1),
+            (Token::Dot(1), 2, 2),
+            (Token::EOF, 2, 3),
+        ]
+    );
+}
+
+#[test]
+fn decomposed_hangul_lexes_like_composed() {
+    // 어떻게 and 엄 spelled as conjoining jamo (NFD)
+    let decomposed = "\u{110B}\u{1165}\u{1104}\u{1165}\u{11C2}\u{1100}\u{1166}\n\u{110B}\u{1165}\u{11B7}.";
+    assert_ne!(decomposed, "어떻게\n엄.");
+    assert_eq!(positions(decomposed), positions("어떻게\n엄."));
+}
+
+#[test]
+fn crlf_and_bare_cr_count_lines_like_lf() {
+    let lines = |source: &str| -> Vec<usize> { positions(source).into_iter().map(|(_, line, _)| line).collect() };
+    let lf = "어떻게\n엄.\n식어!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(positions(&lf.replace('\n', "\r\n")), positions(lf));
+    assert_eq!(positions(&lf.replace('\n', "\r")), positions(lf));
+    assert_eq!(lines(&lf.replace('\n', "\r")), [1, 1, 2, 2, 2, 3, 3, 3, 3, 4, 4]);
+    // A mix of all three still numbers each line once
+    assert_eq!(positions("어떻게\r\n엄.\r식어!\n이 사람이름이냐ㅋㅋ"), positions(lf));
+}
+
+// The expression printed by `식{expr}!` on line 2, lexed with `configure`
+fn printed(expr: &str, configure: impl FnOnce(Lexer) -> Lexer) -> String {
+    let source = format!("어떻게\n식{}!\n이 사람이름이냐ㅋㅋ", expr);
+    let tokens = configure(Lexer::new(&source)).tokenize().unwrap();
+    match Parser::new(tokens).parse().unwrap().statements.remove(0).0 {
+        Statement::PrintNum(expr @ Expr::Mul(..)) => expr.to_string(),
+        Statement::PrintNum(expr) => format!("not a product: {}", expr),
+        other => panic!("expected a print, got {:?}", other),
+    }
+}
+
+#[test]
+fn tab_multiplies_when_enabled() {
+    assert_eq!(printed("..\t...", |lexer| lexer.with_tab_as_space(true)), "2 * 3");
+    // By default a tab is skipped, so the two runs read as one number
+    assert_eq!(printed("..\t...", |lexer| lexer), "not a product: 5");
+}
+
+#[test]
+fn ideographic_space_multiplies_when_enabled() {
+    assert_eq!(printed("..\u{3000}...", |lexer| lexer.with_ideographic_space_as_space(true)), "2 * 3");
+    assert!(tokenize("어떻게\n식..\u{3000}...!\n이 사람이름이냐ㅋㅋ").is_err());
+}
+
+// Characters the lexer treats specially, mixed with a few it rejects
+const ALPHABET: &[char] = &[
+    '어', '엄', '떻', '게', '준', '식', '동', '탄', '화', '이', '팅', '사', '람', '름', '냐', 'ㅋ', '?', '!', '.', ',',
+    '@', '~', ':', ';', '(', ')', '^', '=', '<', '>', '-', '$', '"', '#', '0', '9', ' ', '\t', '\n', '\r', '\u{3000}',
+    '\u{110B}', '\u{1165}', '\u{11B7}', 'a', '가', '😀',
+];
+
+// xorshift64, so every run lexes the same inputs
+fn random_source(state: &mut u64, len: usize) -> String {
+    (0..len)
+        .map(|_| {
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            ALPHABET[(*state % ALPHABET.len() as u64) as usize]
+        })
+        .collect()
+}
+
+#[test]
+fn arbitrary_input_lexes_and_round_trips_to_an_end() {
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    for case in 0..2000 {
+        let source = random_source(&mut state, case % 64);
+        // Reaching the end of either call is the property; errors are fine
+        let Ok(tokens) = tokenize(&source) else {
+            continue;
+        };
+        assert_eq!(tokens.last().map(|token| &token.token), Some(&Token::EOF), "{:?}", source);
+        let _ = tokenize(&tokens_to_source(&tokens));
+    }
+}
+
+#[test]
+fn six_digit_literal_is_one_token() {
+    assert_eq!(
+        positions("엄@123456\n식-@999999,!"),
+        [
+            (Token::Eom(0), 1, 1),
+            (Token::Number(123456), 1, 2),
+            (Token::Newline, 1, 9),
+            (Token::Sik, 2, 1),
+            (Token::Minus, 2, 2),
+            (Token::Number(999999), 2, 3),
+            (Token::Comma(1), 2, 10),
+            (Token::Exclamation, 2, 11),
+            (Token::EOF, 2, 12),
+        ]
+    );
+}
+
+#[test]
+fn long_dot_and_comma_runs_stay_one_token_each() {
+    let source = format!("식{}{}!", ".".repeat(100_000), ",".repeat(100_000));
+    assert_eq!(
+        positions(&source),
+        [
+            (Token::Sik, 1, 1),
+            (Token::Dot(100_000), 1, 2),
+            (Token::Comma(100_000), 1, 100_002),
+            (Token::Exclamation, 1, 200_002),
+            (Token::EOF, 1, 200_003),
+        ]
+    );
+}
+
+#[test]
+fn aliases_expand_only_as_whole_words() {
+    assert_eq!(expand_ascii_aliases("ifa?print.k"), "동탄어?식.ㅋ");
+    assert_eq!(expand_ascii_aliases("aam..."), "어어엄...");
+    for word in ["data", "starts", "printer", "endk_", "a1", "_m", "kind"] {
+        assert_eq!(expand_ascii_aliases(word), word);
+    }
+    // Words end at anything but a letter, digit or `_`
+    assert_eq!(expand_ascii_aliases("data.a!"), "data.어!");
+    assert_eq!(expand_ascii_aliases("m@123456"), "엄@123456");
+}
+
+#[test]
+fn aliased_program_compiles_to_the_hangul_ir() {
+    let hangul = "어떻게\n엄...\n어엄어 어\n동탄어?식어어ㅋ:식.!\n준@5\n이 사람이름이냐ㅋㅋ";
+    let ascii = "start\nm...\nama a\nifa?printaak:print.!\ngoto@5\nend";
+    assert_eq!(expand_ascii_aliases(ascii), hangul);
+    assert_eq!(compile_umjunsik(&expand_ascii_aliases(ascii)).unwrap(), compile_umjunsik(hangul).unwrap());
+}
+
+fn token_kinds(source: &str) -> Vec<Token> {
+    positions(source).into_iter().map(|(token, _, _)| token).collect()
+}
+
+#[test]
+fn tokens_to_source_round_trips() {
+    let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let mut sources = Vec::new();
+    for sub in ["examples", "tests/fixtures"] {
+        for entry in std::fs::read_dir(dir.join(sub)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "umm") {
+                sources.push((path.display().to_string(), std::fs::read_to_string(&path).unwrap()));
+            }
+        }
+    }
+    // Tabs, comments and @ literals, which all change spelling on the way out
+    sources.push(("inline".to_string(), "어떻게\t# start\n엄@1000 ...\n식어,!~식ㅋ\n이 사람이름이냐ㅋㅋ".to_string()));
+
+    for (name, source) in sources {
+        let Ok(tokens) = tokenize(&source) else {
+            continue;
+        };
+        let printed = tokens_to_source(&tokens);
+        assert_eq!(token_kinds(&printed), token_kinds(&source), "{}:\n{}", name, printed);
+    }
+}
+
+#[test]
+fn token_iter_yields_what_tokenize_collects() {
+    let lexer = |source: &str| Lexer::new(source).with_ascii_aliases(true);
+    let key = |token: TokenWithPos| (token.token, token.line, token.col);
+    // A string, comments, aliases and the end marker
+    let source = "start\n식\"Hi\\n\" # greet\nm... # three\nprinta!\n이 사람이름이냐ㅋㅋ";
+    let streamed: Vec<_> = lexer(source).token_iter().map(|token| key(token.unwrap())).collect();
+    let collected: Vec<_> = lexer(source).tokenize().unwrap().into_iter().map(key).collect();
+    assert_eq!(streamed, collected);
+    assert!(collected.iter().any(|(token, _, _)| matches!(token, Token::Str(_))), "{:?}", collected);
+    assert_eq!(collected.last().map(|(token, _, _)| token), Some(&Token::EOF));
+
+    // Both stop at the first error, and the iterator yields nothing after it
+    let source = "어떻게\n식.!\n식😀!\n식..!\n이 사람이름이냐ㅋㅋ";
+    let mut streamed: Vec<_> = Lexer::new(source).token_iter().collect();
+    let error = streamed.pop().unwrap().unwrap_err();
+    assert!(streamed.iter().all(Result::is_ok));
+    assert_eq!(Some(error), tokenize(source).err());
+}
@@ -0,0 +1,19 @@
+//! Warnings from `lint` about conditionals that do nothing.
+
+use umjunsik::{Warning, lint, parse};
+
+fn warnings(source: &str) -> Vec<Warning> {
+    lint(&parse(source).unwrap_or_else(|err| panic!("{:?} failed to parse: {}", source, err)))
+}
+
+#[test]
+fn empty_conditional_warns_once_at_its_line() {
+    let warnings = warnings("어떻게\n엄.\n식어!\n동탄어?\n;\n이 사람이름이냐ㅋㅋ");
+    assert_eq!(warnings, [Warning::EmptyConditional { line: 4 }]);
+    assert_eq!(warnings[0].to_string(), "conditional with empty body has no effect at line 4");
+}
+
+#[test]
+fn empty_body_with_an_else_part_is_deliberate() {
+    assert_eq!(warnings("어떻게\n엄.\n동탄어?:식어!\n이 사람이름이냐ㅋㅋ"), []);
+}
@@ -0,0 +1,18 @@
+//! Several files merged into one program by `parse_multi`.
+
+use umjunsik::parse_multi;
+
+#[test]
+fn merged_lines_follow_each_other_whatever_the_line_endings() {
+    let files = ["어떻게\r\n식.!\r\n이 사람이름이냐ㅋㅋ\r\n", "어떻게\r식..!\r이 사람이름이냐ㅋㅋ\r", "어떻게\n식...!"];
+    let program = parse_multi(&files).unwrap();
+    let lines: Vec<usize> = program.statements.iter().map(|(_, line)| *line).collect();
+    assert_eq!(lines, [2, 5, 8]);
+}
+
+#[test]
+fn gotos_are_rebased_into_their_own_file() {
+    let files = ["어떻게\n식.!\n이 사람이름이냐ㅋㅋ\n", "어떻게\n준...\n식..!\n이 사람이름이냐ㅋㅋ"];
+    let program = parse_multi(&files).unwrap();
+    assert_eq!(program.to_string(), "   2: print_num(1)\n   5: goto line 6\n   6: print_num(2)\n");
+}
@@ -0,0 +1,34 @@
+//! Spaces multiply left to right, each operand being an additive run.
+
+use umjunsik::parse;
+
+// The expression assigned by `어엄{expr}`, as the AST prints it. Operands
+// nested on the right of a `*` print in parentheses, so a left-associative
+// chain prints without any.
+fn assigned(expr: &str) -> String {
+    let source = format!("어떻게\n엄...\n어엄{}\n이 사람이름이냐ㅋㅋ", expr);
+    let program = parse(&source).unwrap_or_else(|err| panic!("{:?} failed to parse: {}", expr, err));
+    program.statements[1].0.to_string()
+}
+
+#[test]
+fn variables() {
+    assert_eq!(assigned("어 어"), "var2 = var1 * var1");
+    assert_eq!(assigned("어 어 어"), "var2 = var1 * var1 * var1");
+}
+
+#[test]
+fn additive_run_then_variable() {
+    assert_eq!(assigned("어.. 어"), "var2 = (var1 + 2) * var1");
+}
+
+#[test]
+fn variables_then_dots() {
+    assert_eq!(assigned("어 어 .."), "var2 = var1 * var1 * 2");
+}
+
+#[test]
+fn trailing_spaces_are_padding() {
+    assert_eq!(assigned("어 어 "), "var2 = var1 * var1");
+    assert_eq!(assigned("어 어  ~식어어!"), "var2 = var1 * var1");
+}
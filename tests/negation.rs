@@ -0,0 +1,35 @@
+//! Unary `-`: negating variables, and constants that fold.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::interp::interpret;
+use umjunsik::parse;
+
+// Prints -var 1, then jumps over line 5 with a negated constant target
+const NEGATIONS: &str = "어떻게\n엄...\n식-어!\n준-(,,,,,,)\n식.!\n식..!\n이 사람이름이냐ㅋㅋ";
+
+#[test]
+fn negated_constant_folds_into_a_goto_target() {
+    assert_eq!(
+        parse(NEGATIONS).unwrap().to_string(),
+        "   2: var1 = 3\n   3: print_num(-var1)\n   4: goto line 6\n   5: print_num(1)\n   6: print_num(2)\n"
+    );
+    let mut stdout = Vec::new();
+    interpret(&parse(NEGATIONS).unwrap(), &mut "".as_bytes(), &mut stdout).unwrap();
+    assert_eq!(String::from_utf8(stdout).unwrap(), "-3\n2\n");
+}
+
+#[test]
+fn negated_variable_is_subtracted_from_zero() {
+    let program = parse(NEGATIONS).unwrap();
+    let ir = CodeGenerator::builder().opt_level(OptLevel::O0).build().generate(&program).unwrap();
+    let line_3: Vec<&str> = ir.split("line_3:").nth(1).unwrap().lines().skip(1).take(3).map(str::trim).collect();
+    assert_eq!(line_3, ["%t0 = load.i64 %var_ptr_0", "%t1 = sub.i64 0, %t0", "print %t1"]);
+}
+
+#[test]
+fn negated_constant_folds_to_one_value() {
+    let program = parse("어떻게\n식-@12!\n식-(.. ...)!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let ir = CodeGenerator::builder().opt_level(OptLevel::O1).build().generate(&program).unwrap();
+    assert!(!ir.contains("sub.i64") && !ir.contains("mul.i64"), "{}", ir);
+    assert!(ir.contains("add.i64 -12, 0") && ir.contains("add.i64 -6, 0"), "{}", ir);
+}
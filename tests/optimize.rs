@@ -0,0 +1,124 @@
+//! IR size and validity at each `OptLevel`.
+
+use umjunsik::ast::Program;
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::{compile_umjunsik, ir_to_assembly, parse};
+
+// Reads five numbers and a byte, and prints products of them and a newline
+const INPUT_HEAVY: &str = "어떻게
+엄식?.....
+어어어어어엄식ㅋ?
+어어어어어어엄어 어어 ...
+식어 어어.!
+식어어어 어어어어 어어어어어!
+식어어어어어어어 어어어어어어!
+식ㅋ
+이 사람이름이냐ㅋㅋ";
+
+fn compile_at_level(program: &Program, opt_level: OptLevel) -> String {
+    CodeGenerator::builder().opt_level(opt_level).build().generate(program).unwrap()
+}
+
+fn compile_at(opt_level: OptLevel) -> String {
+    compile_at_level(&parse(INPUT_HEAVY).unwrap(), opt_level)
+}
+
+#[test]
+fn default_is_o0() {
+    assert_eq!(compile_at(OptLevel::O0), compile_umjunsik(INPUT_HEAVY).unwrap());
+}
+
+#[test]
+fn higher_levels_emit_less_ir() {
+    let [o0, o1, o2] = [OptLevel::O0, OptLevel::O1, OptLevel::O2].map(|level| compile_at(level).lines().count());
+    assert!(o1 < o0, "-O1 emitted {} lines, -O0 {}", o1, o0);
+    assert!(o2 < o1, "-O2 emitted {} lines, -O1 {}", o2, o1);
+}
+
+#[test]
+fn every_level_assembles() {
+    for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+        ir_to_assembly(&compile_at(level)).unwrap_or_else(|err| panic!("{:?} failed to assemble: {}", level, err));
+    }
+}
+
+#[test]
+fn opt_diff_is_empty_without_optimizable_patterns() {
+    // One constant, on line 1, with no variables: -O0 has nothing extra to emit
+    let program = parse("어떻게식\"A\"\n이 사람이름이냐ㅋㅋ").unwrap();
+    assert_eq!(CodeGenerator::builder().opt_diff(&program).unwrap(), "");
+}
+
+#[test]
+fn opt_diff_compares_o0_with_the_chosen_level() {
+    let program = parse(INPUT_HEAVY).unwrap();
+    let diff = CodeGenerator::builder().opt_level(OptLevel::O1).opt_diff(&program).unwrap();
+    assert!(diff.starts_with("--- -O0\n+++ -O1\n@@ -"), "{}", diff);
+}
+
+// The constant operands of every `add.i64 <constant>, 0` in `ir`
+fn materialized_constants(ir: &str) -> Vec<i64> {
+    ir.lines()
+        .filter_map(|line| line.split_once(" = add.i64 "))
+        .filter_map(|(_, operands)| operands.strip_suffix(", 0")?.parse().ok())
+        .collect()
+}
+
+#[test]
+fn constant_expressions_fold_to_one_value() {
+    // 2 * 3 printed, and a condition of 3 - 1
+    let program = parse("어떻게\n식.. ...!\n동탄...,?식.!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let ir = compile_at_level(&program, OptLevel::O1);
+    assert_eq!(materialized_constants(&ir), [6, 2, 1]);
+    assert!(!ir.contains("mul.i64") && !ir.contains("sub.i64"), "{}", ir);
+
+    let ir = compile_at_level(&program, OptLevel::O0);
+    // Unfolded, the product's operands each get a temp
+    assert_eq!(materialized_constants(&ir), [2, 3, 2, 1]);
+}
+
+#[test]
+fn wide_constants_assemble_at_every_level() {
+    // 10^10 folds to a constant wider than any 32-bit immediate
+    let program = parse("어떻게\n식@100000 @100000!\n엄@3037000500\n식어 어!\n이 사람이름이냐ㅋㅋ").unwrap();
+    for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+        let ir = compile_at_level(&program, level);
+        assert!(
+            materialized_constants(&ir).iter().all(|&value| i32::try_from(value).is_ok()),
+            "{:?}: {}",
+            level,
+            ir
+        );
+        ir_to_assembly(&ir).unwrap_or_else(|err| panic!("{:?} failed to assemble: {}", level, err));
+    }
+}
+
+#[test]
+fn repeated_constant_reuses_its_temp_within_a_block() {
+    let program = parse("어떻게\n엄식?\n식어 @48 어 @48!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let line_3 = |ir: &str| ir.split("line_3:").nth(1).unwrap().to_string();
+
+    let ir = line_3(&compile_at_level(&program, OptLevel::O1));
+    assert_eq!(materialized_constants(&ir), [48], "{}", ir);
+    let temp = ir.lines().find_map(|line| line.trim().strip_suffix(" = add.i64 48, 0")).unwrap();
+    assert_eq!(ir.matches(&format!(", {}\n", temp)).count(), 2, "{}", ir);
+
+    let ir = line_3(&compile_at_level(&program, OptLevel::O0));
+    assert_eq!(materialized_constants(&ir), [48, 48], "{}", ir);
+}
+
+#[test]
+fn newline_constant_is_materialized_once() {
+    let program = parse("어떻게\n식ㅋ\n식ㅋ\n식ㅋ\n이 사람이름이냐ㅋㅋ").unwrap();
+    for level in [OptLevel::O1, OptLevel::O2] {
+        let ir = compile_at_level(&program, level);
+        assert_eq!(materialized_constants(&ir), [10], "{:?}: {}", level, ir);
+        assert_eq!(ir.matches("writebyte %nl_const\n").count(), 3, "{:?}: {}", level, ir);
+    }
+    let ir = compile_at_level(&program, OptLevel::O0);
+    assert_eq!(materialized_constants(&ir), [10, 10, 10], "{}", ir);
+
+    // Nothing prints a newline, so nothing is hoisted
+    let ir = compile_at_level(&parse("어떻게\n식.!\n이 사람이름이냐ㅋㅋ").unwrap(), OptLevel::O2);
+    assert!(!ir.contains("%nl_const"), "{}", ir);
+}
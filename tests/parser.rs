@@ -0,0 +1,235 @@
+//! Statement and expression forms as the parser reads them, and its errors.
+
+use umjunsik::interp::interpret;
+use umjunsik::parser::Parser;
+use umjunsik::token::Token;
+use umjunsik::{CompileError, compile_umjunsik, parse, tokenize};
+
+// The program's statements as the AST prints them, one per line
+fn ast(source: &str) -> String {
+    parse(source).unwrap_or_else(|err| panic!("{:?} failed to parse: {}", source, err)).to_string()
+}
+
+fn parse_error(source: &str) -> (String, Option<(usize, usize)>) {
+    let err = parse(source).expect_err("source should fail to parse");
+    (err.to_string(), err.location())
+}
+
+fn run(source: &str, input: &str) -> String {
+    let program = parse(source).unwrap_or_else(|err| panic!("{:?} failed to parse: {}", source, err));
+    let mut stdout = Vec::new();
+    interpret(&program, &mut input.as_bytes(), &mut stdout).unwrap();
+    String::from_utf8(stdout).unwrap()
+}
+
+#[test]
+fn print_without_expression() {
+    assert_eq!(
+        parse_error("어떻게\n식!\n이 사람이름이냐ㅋㅋ"),
+        ("식! requires an expression to print".to_string(), Some((2, 2)))
+    );
+}
+
+#[test]
+fn print_one() {
+    let source = "어떻게\n식.!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(ast(source), "   2: print_num(1)\n");
+    assert_eq!(run(source, ""), "1\n");
+}
+
+#[test]
+fn grouped_sum_is_multiplied() {
+    let source = "어떻게\n엄...\n식(어 어). ..!\n식어 어. ..!\n식(.. ..) ...!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        ast(source),
+        "   2: var1 = 3\n   3: print_num((var1 * var1 + 1) * 2)\n   4: print_num(var1 * (var1 + 1) * 2)\n   \
+         5: print_num(2 * 2 * 3)\n"
+    );
+    assert_eq!(run(source, ""), "20\n24\n12\n");
+}
+
+#[test]
+fn goto_into_joined_line_resumes_at_its_first_statement() {
+    let source = "어떻게\n엄...\n식어!~엄어,\n동탄어?식.!:준...\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        ast(source),
+        "   2: var1 = 3\n   3: print_num(var1)\n   3: var1 = var1 - 1\n   \
+         4: if var1 == 0 { print_num(1) } else { goto line 3 }\n"
+    );
+    // Landing on the decrement instead would skip the prints of 2 and 1
+    assert_eq!(run(source, ""), "3\n2\n1\n1\n");
+}
+
+#[test]
+fn assignment_past_the_variable_limit() {
+    // 10000 어s before 엄 assign var 10001, one past the default limit
+    let source = format!("어떻게\n{}엄.\n이 사람이름이냐ㅋㅋ", "어".repeat(10000));
+    assert_eq!(
+        parse_error(&source),
+        ("Variable index 10001 exceeds the limit of 10000".to_string(), Some((2, 1)))
+    );
+    assert!(parse(&format!("어떻게\n{}엄.\n이 사람이름이냐ㅋㅋ", "어".repeat(9999))).is_ok());
+}
+
+#[test]
+fn configured_variable_limit() {
+    let limited = |source: &str| {
+        Parser::new(tokenize(source).unwrap()).with_max_var_index(3).parse().map_err(|err| err.to_string())
+    };
+    assert!(limited("어떻게\n어어엄.\n식어어어!\n이 사람이름이냐ㅋㅋ").is_ok());
+    assert_eq!(
+        limited("어떻게\n어어어엄.\n이 사람이름이냐ㅋㅋ").unwrap_err(),
+        "Variable index 4 exceeds the limit of 3"
+    );
+    assert_eq!(
+        limited("어떻게\n식어어어어!\n이 사람이름이냐ㅋㅋ").unwrap_err(),
+        "Variable index 4 exceeds the limit of 3"
+    );
+}
+
+#[test]
+fn dot_and_comma_runs_fold_around_variables_in_order() {
+    let source = "어떻게\n엄.....\n식어...,!\n식...어!\n식..어,,,!\n식.,어..,어!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        ast(source),
+        "   2: var1 = 5\n   3: print_num(var1 + 3 - 1)\n   4: print_num(3 + var1)\n   \
+         5: print_num(2 + var1 - 3)\n   6: print_num(0 + var1 + 2 - 1 + var1)\n"
+    );
+    assert_eq!(run(source, ""), "7\n8\n4\n11\n");
+}
+
+// The errors, and the statements that did parse
+fn recovering(source: &str) -> (Vec<CompileError>, String) {
+    let (program, errors) = Parser::new(tokenize(source).unwrap()).parse_recovering();
+    (errors, program.unwrap().to_string())
+}
+
+#[test]
+fn recovering_parse_reports_every_broken_line() {
+    let source = "어떻게\n식!\n식.!\n식!\n이 사람이름이냐ㅋㅋ";
+    let (errors, statements) = recovering(source);
+    let errors: Vec<_> = errors.iter().map(|err| (err.to_string(), err.location())).collect();
+    let error = "식! requires an expression to print".to_string();
+    assert_eq!(errors, [(error.clone(), Some((2, 2))), (error, Some((4, 2)))]);
+    assert_eq!(statements, "   3: print_num(1)\n");
+    // `parse` stops at the first
+    assert_eq!(parse_error(source).1, Some((2, 2)));
+}
+
+#[test]
+fn recovering_parse_resumes_after_a_tilde() {
+    let source = "어떻게\n식!~식.!~식!\n이 사람이름이냐ㅋㅋ";
+    let (errors, statements) = recovering(source);
+    let locations: Vec<_> = errors.iter().map(CompileError::location).collect();
+    assert_eq!(locations, [Some((2, 2)), Some((2, 9))]);
+    assert_eq!(statements, "   2: print_num(1)\n");
+}
+
+#[test]
+fn recovering_parse_without_a_header() {
+    let (program, errors) = Parser::new(tokenize("식.!\n이 사람이름이냐ㅋㅋ").unwrap()).parse_recovering();
+    assert!(program.is_none());
+    assert_eq!(errors[0].to_string(), "program must begin with 어떻게");
+}
+
+#[test]
+fn spaces_before_the_print_end_do_not_multiply() {
+    let source = "어떻게\n엄...\n식 어 !\n식어 .!\n식어 ..!\n식어 ㅋ\n식 어어  !\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        ast(source),
+        "   2: var1 = 3\n   3: print_num(var1)\n   4: print_num(var1 * 1)\n   5: print_num(var1 * 2)\n   \
+         6: print_char(var1)\n   7: print_num(var2)\n"
+    );
+    assert_eq!(run(source, ""), "3\n3\n6\n\u{3}0\n");
+}
+
+#[test]
+fn large_literals() {
+    let source = "어떻게\n엄@123456\n식어 @3037000500!\n식-@999999,!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        ast(source),
+        "   2: var1 = 123456\n   3: print_num(var1 * 3037000500)\n   4: print_num(-999999 - 1)\n"
+    );
+    assert_eq!(run(source, ""), "374935933728000\n-1000000\n");
+}
+
+#[test]
+fn number_token_reaches_codegen() {
+    let source = "어떻게\n엄..\n준@5\n식.!\n식@1000000 어!\n이 사람이름이냐ㅋㅋ";
+    let tokens: Vec<Token> = tokenize(source).unwrap().into_iter().map(|token| token.token).collect();
+    assert_eq!(tokens.iter().filter(|token| matches!(token, Token::Number(_))).count(), 2);
+    assert_eq!(
+        ast(source),
+        "   2: var1 = 2\n   3: goto line 5\n   4: print_num(1)\n   5: print_num(1000000 * var1)\n"
+    );
+    assert_eq!(run(source, ""), "2000000\n");
+    let ir = compile_umjunsik(source).unwrap();
+    assert!(ir.contains("add.i64 1000000, 0"), "{}", ir);
+}
+
+#[test]
+fn lone_k_gets_a_targeted_error() {
+    assert_eq!(
+        parse_error("어떻게\n식.!\nㅋㅋ\n이 사람이름이냐ㅋㅋ"),
+        ("Unexpected ㅋ outside a 식 statement or the end marker".to_string(), Some((3, 1)))
+    );
+    assert_eq!(
+        parse_error("어떻게\n식.!~ㅋ\n이 사람이름이냐ㅋㅋ"),
+        ("Unexpected ㅋ outside a 식 statement or the end marker".to_string(), Some((2, 5)))
+    );
+}
+
+#[test]
+fn expression_statement_starts_with_input() {
+    // The first two integers are read and dropped, so 7 reaches var 1
+    let source = "어떻게\n식?\n식? 어,\n엄식?\n식어!\n이 사람이름이냐ㅋㅋ";
+    assert_eq!(
+        ast(source),
+        "   2: eval(input())\n   3: eval(input() * (var1 - 1))\n   4: var1 = input()\n   5: print_num(var1)\n"
+    );
+    assert_eq!(run(source, "5 6 7"), "7\n");
+    let error = "An expression statement must start with 식? (assign other values with 엄 or print them with 식)";
+    for line in ["어 어어", "(어,)%..", "어"] {
+        let source = format!("어떻게\n엄...\n{}\n이 사람이름이냐ㅋㅋ", line);
+        assert_eq!(parse_error(&source), (error.to_string(), Some((3, 1))), "{}", line);
+    }
+}
+
+// Two-statement bodies and else parts, and a block nested in a block
+const BLOCKS: &str = "어떻게\n엄...\n동탄어,,,?\n식.!\n식..!\n:\n식...!\n;\n동탄어?\n식@10!\n:\n식@20!\n식@30!\n;\n\
+                      동탄어,,,?\n식.!\n동탄어?\n식..!\n:\n식...!\n;\n식....!\n;\n이 사람이름이냐ㅋㅋ";
+
+#[test]
+fn block_conditionals_hold_several_statements() {
+    assert_eq!(
+        ast(BLOCKS),
+        "   2: var1 = 3\n   3: if var1 - 3 == 0 { print_num(1); print_num(2) } else { print_num(3) }\n   \
+         9: if var1 == 0 { print_num(10) } else { print_num(20); print_num(30) }\n  \
+         15: if var1 - 3 == 0 { print_num(1); if var1 == 0 { print_num(2) } else { print_num(3) }; print_num(4) }\n"
+    );
+    assert_eq!(run(BLOCKS, ""), "1\n2\n20\n30\n1\n3\n4\n");
+}
+
+#[test]
+fn goto_target_product_that_overflows() {
+    // 3037000500² is just past i64::MAX; the error points past the target
+    assert_eq!(
+        parse_error("어떻게\n식.!\n준@3037000500 @3037000500\n이 사람이름이냐ㅋㅋ"),
+        ("goto target expression overflows".to_string(), Some((3, 25)))
+    );
+    // One less squared fits, and is then just a line that doesn't exist
+    assert!(parse("어떻게\n식.!\n준@3037000499 @3037000499\n이 사람이름이냐ㅋㅋ").is_ok());
+}
+
+#[test]
+fn empty_file_or_no_header() {
+    let both = "program must begin with 어떻게 and end with 이 사람이름이냐ㅋㅋ".to_string();
+    assert_eq!(parse_error(""), (both.clone(), Some((1, 1))));
+    assert_eq!(parse_error("\n\n"), (both.clone(), Some((1, 1))));
+    assert_eq!(parse_error("식.!\n식..!"), (both, Some((1, 1))));
+    // With the end marker present only the header is missing
+    assert_eq!(
+        parse_error("식.!\n이 사람이름이냐ㅋㅋ"),
+        ("program must begin with 어떻게".to_string(), Some((1, 1)))
+    );
+}
@@ -0,0 +1,25 @@
+//! `--portable-print`: numbers written digit by digit with `writebyte`.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::parse;
+
+#[test]
+fn negative_number_writes_a_sign_then_digits() {
+    let program = parse("어떻게\n식-@123!\n이 사람이름이냐ㅋㅋ").unwrap();
+    let ir = CodeGenerator::builder()
+        .portable_print(true)
+        .opt_level(OptLevel::O0)
+        .build()
+        .generate(&program)
+        .unwrap();
+    assert!(!ir.lines().any(|line| line.trim().starts_with("print ")), "{}", ir);
+
+    // The sign block writes '-' and nothing else
+    let sign: Vec<&str> = ir.split("print_sign_0:").nth(1).unwrap().lines().skip(1).take(3).map(str::trim).collect();
+    assert_eq!(sign, ["%t3 = add.i64 45, 0", "%t4 = writebyte %t3", "jmp print_split_2"]);
+    // Every digit is a '0' offset by a remainder of 10
+    for block in ["print_digit_5:", "print_last_6:"] {
+        let digit = ir.split(block).nth(1).unwrap();
+        assert!(digit.contains("= add.i64 48, 0\n") && digit.contains("= writebyte "), "{}", ir);
+    }
+}
@@ -0,0 +1,39 @@
+//! `식{expr}!{radix}`: numbers printed in base 2 to 16.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::interp::interpret;
+use umjunsik::parse;
+
+const HEX_255: &str = "어떻게\n식@255!@16\n이 사람이름이냐ㅋㅋ";
+
+fn run(source: &str) -> String {
+    let program = parse(source).unwrap_or_else(|err| panic!("{:?} failed to parse: {}", source, err));
+    let mut stdout = Vec::new();
+    interpret(&program, &mut "".as_bytes(), &mut stdout).unwrap();
+    String::from_utf8(stdout).unwrap()
+}
+
+#[test]
+fn prints_255_in_base_16_as_ff() {
+    assert_eq!(run(HEX_255), "ff\n");
+    assert_eq!(run("어떻게\n식-@255!@16\n식.....!..\n식@0!@2\n이 사람이름이냐ㅋㅋ"), "-ff\n101\n0\n");
+}
+
+#[test]
+fn radix_digits_are_written_byte_by_byte() {
+    let program = parse(HEX_255).unwrap();
+    let ir = CodeGenerator::builder().opt_level(OptLevel::O0).build().generate(&program).unwrap();
+    assert!(!ir.lines().any(|line| line.trim().starts_with("print ")), "{}", ir);
+    assert!(ir.contains("writebyte"), "{}", ir);
+    // A digit past 9 skips the 39 bytes between '9' + 1 and 'a'
+    assert!(ir.contains("add.i64 48, 0\n") && ir.contains(", 39\n"), "{}", ir);
+}
+
+#[test]
+fn radix_outside_2_to_16_is_rejected() {
+    for (radix, shown) in [("@17", 17), (".", 1), ("@0", 0)] {
+        let err = parse(&format!("어떻게\n식@255!{}\n이 사람이름이냐ㅋㅋ", radix)).unwrap_err();
+        assert_eq!(err.to_string(), format!("Print radix must be between 2 and 16, got {}", shown));
+        assert_eq!(err.location(), Some((2, 7)));
+    }
+}
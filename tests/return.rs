@@ -0,0 +1,42 @@
+//! `화이팅!` with a variable: the value is loaded, returned, and nothing follows the `ret`.
+
+use umjunsik::{compile_umjunsik, ir_to_assembly};
+
+// The goto keeps var 1 in a stack slot rather than an SSA temp
+const RETURN_VAR: &str = "어떻게\n엄...\n동탄어?준@4\n화이팅!어\n이 사람이름이냐ㅋㅋ";
+
+// A return in a 동탄 body, with a statement after it that can't run
+const RETURN_IN_BODY: &str = "어떻게
+엄...
+동탄어?준@4
+동탄어,,,?화이팅!어식..!:식...!
+식....!
+이 사람이름이냐ㅋㅋ";
+
+// Each `ret.i64` line with the line before it and the first nonblank line after it
+fn returns(ir: &str) -> Vec<(&str, &str, &str)> {
+    let lines: Vec<&str> = ir.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    (1..lines.len() - 1)
+        .filter(|&idx| lines[idx].starts_with("ret.i64 "))
+        .map(|idx| (lines[idx - 1], lines[idx], lines[idx + 1]))
+        .collect()
+}
+
+#[test]
+fn variable_return_loads_then_returns() {
+    let ir = compile_umjunsik(RETURN_VAR).unwrap();
+    assert_eq!(returns(&ir), [("%t2 = load.i64 %var_ptr_0", "ret.i64 %t2", "}")]);
+}
+
+#[test]
+fn return_in_body_ends_its_block() {
+    let ir = compile_umjunsik(RETURN_IN_BODY).unwrap();
+    let found = returns(&ir);
+    assert_eq!(found.len(), 2, "{}", ir);
+    let (load, ret, after) = found[0];
+    assert!(load.ends_with("= load.i64 %var_ptr_0"), "{}", load);
+    assert_eq!(ret, format!("ret.i64 {}", load.split(' ').next().unwrap()));
+    // The dropped print leaves the else block's label right after the return
+    assert!(after.starts_with("else_") && after.ends_with(':'), "{}", after);
+    ir_to_assembly(&ir).unwrap();
+}
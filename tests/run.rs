@@ -0,0 +1,251 @@
+//! End to end: programs built and executed with `--run` at every `-O` level
+//! print what `--interpret` prints for the same input, and exit the same way.
+//!
+//! Linking needs a C compiler; without one on the PATH these tests pass
+//! without running anything.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use umjunsik::{C_COMPILER_CANDIDATES, parse, select_c_compiler};
+
+const INPUT: &str = "3 4 5 6\n";
+
+fn c_compiler() -> Option<String> {
+    let runs = |cc: &str| {
+        Command::new(cc)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    };
+    let cc = select_c_compiler(None, None, runs);
+    if cc.is_none() {
+        eprintln!("no C compiler found (tried {}); skipping", C_COMPILER_CANDIDATES.join(", "));
+    }
+    cc
+}
+
+fn umjunsik(path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_umjunsik"))
+        .args(args)
+        .args(["--quiet", "--stdin-input", INPUT])
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+// Runs `path` natively at each level and compares it with the interpreter
+fn assert_runs_like_interpreter(path: &Path, cc: &str, extra_args: &[&str]) {
+    let interpreted = umjunsik(path, &[&["--interpret"], extra_args].concat());
+    for level in ["-O0", "-O1", "-O2"] {
+        let compiled = umjunsik(path, &[&["--run", "--cc", cc, level], extra_args].concat());
+        assert_eq!(
+            String::from_utf8_lossy(&compiled.stdout),
+            String::from_utf8_lossy(&interpreted.stdout),
+            "{} at {}: {}",
+            path.display(),
+            level,
+            String::from_utf8_lossy(&compiled.stderr)
+        );
+        assert_eq!(compiled.status.code(), interpreted.status.code(), "{} at {}", path.display(), level);
+    }
+}
+
+// Writes `source` to a temp file named after the calling test
+fn temp_source(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("umjunsik-run-{}-{}.umm", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn examples_run_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let examples = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut paths: Vec<PathBuf> = fs::read_dir(examples).unwrap().map(|entry| entry.unwrap().path()).collect();
+    paths.sort();
+    for path in paths {
+        // Some examples spell 화이팅 in a form the parser rejects; nothing runs those
+        if parse(&fs::read_to_string(&path).unwrap()).is_err() {
+            continue;
+        }
+        assert_runs_like_interpreter(&path, &cc, &[]);
+    }
+}
+
+#[test]
+fn wide_constants_run_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let path = temp_source(
+        "wide",
+        "어떻게\n식@100000 @100000!\n엄@3037000500\n식어 어!\n식-@9223372036854775807,!\n이 사람이름이냐ㅋㅋ",
+    );
+    assert_runs_like_interpreter(&path, &cc, &[]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn goto_into_joined_line_runs_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let path = temp_source("joined", "어떻게\n엄...\n식어!~엄어,\n동탄어?식.!:준...\n이 사람이름이냐ㅋㅋ");
+    assert_runs_like_interpreter(&path, &cc, &[]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn portable_print_runs_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    // Negative, zero, single and multi-digit values, and both ends of i64
+    let path = temp_source(
+        "portable",
+        "어떻게\n식-@123!\n식@0!\n식.......!\n식-@10!\n식@100!\n식@9223372036854775807!\n\
+         식-@9223372036854775807,!\n이 사람이름이냐ㅋㅋ",
+    );
+    let interpreted = umjunsik(&path, &["--interpret"]);
+    assert_eq!(
+        String::from_utf8_lossy(&interpreted.stdout),
+        "-123\n0\n7\n-10\n100\n9223372036854775807\n-9223372036854775808\n"
+    );
+    assert_runs_like_interpreter(&path, &cc, &["--portable-print"]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn truthy_conditionals_and_max_lines_run_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let path = temp_source("limits", "어떻게\n엄...\n식어!~엄어,\n동탄어?화이팅!..:준...\n이 사람이름이냐ㅋㅋ");
+    for args in [&["--truthy-conditionals"][..], &["--max-lines", "9"], &["--max-lines", "10"], &["--max-lines", "4"]] {
+        assert_runs_like_interpreter(&path, &cc, args);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn radix_print_runs_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let path = temp_source("radix", "어떻게\n식@255!@16\n식-@255!@16\n식.....!..\n식@0!@2\n이 사람이름이냐ㅋㅋ");
+    assert_runs_like_interpreter(&path, &cc, &[]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn max_lines_stops_an_infinite_loop() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let path = temp_source("forever", "어떻게\n준..\n이 사람이름이냐ㅋㅋ");
+    let compiled = umjunsik(&path, &["--run", "--cc", &cc, "--max-lines", "100"]);
+    fs::remove_file(path).unwrap();
+    assert_eq!(String::from_utf8_lossy(&compiled.stdout), "line limit exceeded\n");
+    assert_eq!(compiled.status.code(), Some(1));
+}
+
+#[test]
+fn checked_arithmetic_runs_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let programs = [
+        // Squares var 1 in a goto loop until the product overflows
+        "어떻게\n엄...\n엄어 어\n식어!\n준...\n이 사람이름이냐ㅋㅋ",
+        // One past i64::MAX, below i64::MIN, and the negation of i64::MIN
+        "어떻게\n엄@9223372036854775807\n식어.!\n이 사람이름이냐ㅋㅋ",
+        "어떻게\n엄-@9223372036854775807,\n식어,!\n이 사람이름이냐ㅋㅋ",
+        "어떻게\n엄-@9223372036854775807,\n식-어!\n이 사람이름이냐ㅋㅋ",
+        // The remainder of i64::MIN by -1, which overflows the division behind it
+        "어떻게\n엄-@9223372036854775807,\n어엄,\n식어%어어!\n이 사람이름이냐ㅋㅋ",
+        // Remainders that stay in range, by -1 included
+        "어떻게\n엄-@7\n어엄,\n식어%어어!\n식어%...!\n식(-@9223372036854775807)%어어!\n이 사람이름이냐ㅋㅋ",
+        // Arithmetic on input that stays in range
+        "어떻게\n엄식?\n어엄식?\n식어 어어 어어,!\n식-어^...!\n이 사람이름이냐ㅋㅋ",
+    ];
+    for (idx, source) in programs.iter().enumerate() {
+        let path = temp_source(&format!("checked{}", idx), source);
+        assert_runs_like_interpreter(&path, &cc, &["--checked"]);
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn input_count_runs_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    let path = temp_source("input_count", "어떻게\n어엄식?...\n식어어 어어어 어어어어!\n식어어어어!\n이 사람이름이냐ㅋㅋ");
+    let interpreted = umjunsik(&path, &["--interpret"]);
+    assert_eq!(String::from_utf8_lossy(&interpreted.stdout), "60\n5\n");
+    assert_runs_like_interpreter(&path, &cc, &[]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn max_lines_counts_threaded_gotos() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    // -O2 would thread lines 3 to 5 into one jump back to line 2
+    let path = temp_source("chain", "어떻게\n식.!\n준....\n준.....\n준..\n이 사람이름이냐ㅋㅋ");
+    let interpreted = umjunsik(&path, &["--interpret", "--max-lines", "10"]);
+    assert_eq!(String::from_utf8_lossy(&interpreted.stdout), "1\n1\n1\nline limit exceeded\n");
+    assert_runs_like_interpreter(&path, &cc, &["--max-lines", "10"]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn block_conditionals_run_like_interpreter() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    // The program of tests/parser.rs, with var 1 read from input so nothing folds away
+    let path = temp_source(
+        "blocks",
+        "어떻게\n엄식?\n동탄어,,,?\n식.!\n식..!\n:\n식...!\n;\n동탄어?\n식@10!\n:\n식@20!\n식@30!\n;\n\
+         동탄어,,,?\n식.!\n동탄어?\n식..!\n:\n식...!\n;\n식....!\n;\n이 사람이름이냐ㅋㅋ",
+    );
+    let interpreted = umjunsik(&path, &["--interpret"]);
+    assert_eq!(String::from_utf8_lossy(&interpreted.stdout), "1\n2\n20\n30\n1\n3\n4\n");
+    assert_runs_like_interpreter(&path, &cc, &[]);
+    fs::remove_file(path).unwrap();
+}
+
+// The exit status of a program returning `value`, run natively
+fn exit_status(cc: &str, name: &str, value: &str) -> Option<i32> {
+    let path = temp_source(name, &format!("어떻게\n식.!\n화이팅!{}\n이 사람이름이냐ㅋㅋ", value));
+    let compiled = umjunsik(&path, &["--run", "--cc", cc]);
+    assert_eq!(String::from_utf8_lossy(&compiled.stdout), "1\n", "{}", String::from_utf8_lossy(&compiled.stderr));
+    assert_runs_like_interpreter(&path, cc, &[]);
+    fs::remove_file(path).unwrap();
+    compiled.status.code()
+}
+
+#[test]
+fn return_value_is_the_exit_status() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    assert_eq!(exit_status(&cc, "return3", "..."), Some(3));
+}
+
+#[test]
+fn return_value_keeps_only_its_low_8_bits() {
+    let Some(cc) = c_compiler() else {
+        return;
+    };
+    assert_eq!(exit_status(&cc, "return256", "@256"), Some(0));
+    assert_eq!(exit_status(&cc, "return257", "@257"), Some(1));
+    assert_eq!(exit_status(&cc, "return_negative", ","), Some(255));
+}
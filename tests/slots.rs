@@ -0,0 +1,76 @@
+//! Where variables live: stack slots, shared slots, or SSA temps.
+
+use umjunsik::codegen::{CodeGenerator, OptLevel};
+use umjunsik::parse;
+
+// Var 1 is done with by line 4, and line 5 overwrites var 2 before reading it.
+// Both are written in a conditional, so both need a slot.
+const SEQUENTIAL: &str = "어떻게
+엄식?
+동탄어?엄.
+식어!
+어엄..
+동탄어어?어엄...
+식어어!
+이 사람이름이냐ㅋㅋ";
+
+fn compile_at(source: &str, opt_level: OptLevel) -> String {
+    let program = parse(source).unwrap_or_else(|err| panic!("failed to parse: {}", err));
+    CodeGenerator::builder().opt_level(opt_level).build().generate(&program).unwrap()
+}
+
+fn slot_comments(ir: &str) -> Vec<&str> {
+    ir.lines().map(str::trim).filter(|line| line.starts_with("# %var_ptr_")).collect()
+}
+
+#[test]
+fn variables_with_disjoint_lifetimes_share_a_slot() {
+    assert_eq!(slot_comments(&compile_at(SEQUENTIAL, OptLevel::O2)), ["# %var_ptr_0: var1, var2"]);
+    assert_eq!(
+        slot_comments(&compile_at(SEQUENTIAL, OptLevel::O0)),
+        ["# %var_ptr_0: var1", "# %var_ptr_1: var2"]
+    );
+}
+
+#[test]
+fn goto_keeps_every_variable_in_its_own_slot() {
+    // Looping back to line 2 makes var 1 live again after var 2
+    let source = SEQUENTIAL.replace("식어어!", "식어어!~준..");
+    assert_eq!(
+        slot_comments(&compile_at(&source, OptLevel::O2)),
+        ["# %var_ptr_0: var1", "# %var_ptr_1: var2"]
+    );
+}
+
+#[test]
+fn goto_free_variables_live_in_ssa_temps() {
+    // Var 2 = var 1 squared, with no goto anywhere
+    let straight = "어떻게\n엄식?\n어엄어 어\n식어어!\n이 사람이름이냐ㅋㅋ";
+    let ir = compile_at(straight, OptLevel::O2);
+    assert!(slot_comments(&ir).is_empty(), "{}", ir);
+    assert!(!ir.contains("%var_ptr_"), "{}", ir);
+
+    // The same program looping back to line 2 keeps both variables in memory
+    let looping = straight.replace("식어어!", "식어어!~준..");
+    let ir = compile_at(&looping, OptLevel::O2);
+    assert_eq!(slot_comments(&ir).len(), 2, "{}", ir);
+    assert!(ir.contains("store.i64 %var_ptr_"), "{}", ir);
+}
+
+#[test]
+fn sparse_variables_get_dense_slots() {
+    // Vars 1, 7 and 100, each assigned and then all multiplied
+    let source = format!(
+        "어떻게\n엄.\n{}엄..\n{}엄...\n식어 {} {}!\n이 사람이름이냐ㅋㅋ",
+        "어".repeat(6),
+        "어".repeat(99),
+        "어".repeat(7),
+        "어".repeat(100)
+    );
+    let ir = compile_at(&source, OptLevel::O0);
+    assert_eq!(
+        slot_comments(&ir),
+        ["# %var_ptr_0: var1", "# %var_ptr_1: var7", "# %var_ptr_2: var100"]
+    );
+    assert!(!ir.contains("%var_ptr_3"), "{}", ir);
+}
@@ -0,0 +1,117 @@
+//! Snapshot tests for the generated Lamina IR.
+//!
+//! Each `tests/fixtures/<name>.umm` is compiled with `compile_umjunsik` and
+//! compared byte for byte with `tests/snapshots/<name>.lamina`. After an
+//! intended codegen change, rerun with `UPDATE_SNAPSHOTS=1` to rewrite the
+//! snapshots and review the diff along with the change.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use umjunsik::compile_umjunsik;
+
+fn compile_fixture(name: &str) -> String {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{}.umm", name));
+    let source = fs::read_to_string(&fixture)
+        .unwrap_or_else(|err| panic!("Error reading fixture '{}': {}", fixture.display(), err));
+    compile_umjunsik(&source).unwrap_or_else(|err| panic!("{} failed to compile: {}", name, err))
+}
+
+fn assert_snapshot(name: &str) {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let snapshot = root.join("snapshots").join(format!("{}.lamina", name));
+    let actual = compile_fixture(name);
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(root.join("snapshots")).unwrap();
+        fs::write(&snapshot, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&snapshot).unwrap_or_else(|err| {
+        panic!(
+            "Error reading snapshot '{}': {} (run with UPDATE_SNAPSHOTS=1 to create it)",
+            snapshot.display(),
+            err
+        )
+    });
+    if actual == expected {
+        return;
+    }
+
+    // Point at the first differing line rather than dumping both modules
+    let mismatch = actual
+        .lines()
+        .zip(expected.lines())
+        .position(|(a, e)| a != e)
+        .unwrap_or_else(|| actual.lines().count().min(expected.lines().count()));
+    panic!(
+        "IR for {} differs from {} at line {}\n  expected: {:?}\n    actual: {:?}\n\
+         (run with UPDATE_SNAPSHOTS=1 if the change is intended)",
+        name,
+        snapshot.display(),
+        mismatch + 1,
+        expected.lines().nth(mismatch).unwrap_or("<end of snapshot>"),
+        actual.lines().nth(mismatch).unwrap_or("<end of output>"),
+    );
+}
+
+#[test]
+fn hello() {
+    assert_snapshot("hello");
+}
+
+#[test]
+fn input_arith() {
+    assert_snapshot("input_arith");
+}
+
+#[test]
+fn goto_loop() {
+    assert_snapshot("goto_loop");
+}
+
+#[test]
+fn conditional() {
+    assert_snapshot("conditional");
+}
+
+#[test]
+fn string_literal() {
+    assert_snapshot("string_literal");
+}
+
+#[test]
+fn unreachable() {
+    assert_snapshot("unreachable");
+}
+
+#[test]
+fn assignment_targets() {
+    assert_snapshot("assignment_targets");
+}
+
+#[test]
+fn while_loop() {
+    assert_snapshot("while_loop");
+    // The loop lowers to exactly the conditional and gotos it replaces
+    assert_eq!(compile_fixture("while_loop"), compile_fixture("while_goto"));
+}
+
+#[test]
+fn eval() {
+    assert_snapshot("eval");
+}
+
+#[test]
+fn input_expr() {
+    assert_snapshot("input_expr");
+    // The reader's result goes straight to the one print, without a variable
+    let ir = compile_fixture("input_expr");
+    let lines: Vec<&str> = ir.lines().map(str::trim).collect();
+    assert_eq!(lines.iter().filter(|line| line.starts_with("input_skip_ws_") && line.ends_with(':')).count(), 1);
+    assert_eq!(lines.iter().filter(|line| line.starts_with("print ")).count(), 1);
+    assert!(!ir.contains("%var_ptr"));
+}
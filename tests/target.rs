@@ -0,0 +1,35 @@
+//! Parsing and printing `Target` names.
+
+use umjunsik::{Target, host_target};
+
+#[test]
+fn parses_every_lamina_target() {
+    for name in lamina::HOST_ARCH_LIST {
+        let target: Target = name.parse().unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(target.to_string(), *name);
+    }
+    assert_eq!(Target::ALL.len(), lamina::HOST_ARCH_LIST.len());
+}
+
+#[test]
+fn parses_valid_names() {
+    assert_eq!("x86_64_linux".parse(), Ok(Target::X86_64Linux));
+    assert_eq!("aarch64_macos".parse(), Ok(Target::Aarch64Macos));
+}
+
+#[test]
+fn rejects_invalid_names() {
+    for name in ["", "x86_64", "X86_64_LINUX", "riscv64_linux", " x86_64_linux"] {
+        let err = name.parse::<Target>().unwrap_err();
+        assert_eq!(err.0, name);
+        let message = err.to_string();
+        assert!(message.starts_with(&format!("unknown target '{}'", name)), "{}", message);
+        assert!(message.contains("x86_64_linux, x86_64_macos"), "{}", message);
+    }
+}
+
+#[test]
+fn host_is_a_lamina_target() {
+    let host = host_target().unwrap_or_else(|err| panic!("{}", err));
+    assert!(lamina::HOST_ARCH_LIST.contains(&host.lamina_name()));
+}
@@ -0,0 +1,113 @@
+//! One test per `validate` failure category, checking the error and where it points.
+
+use std::collections::BTreeMap;
+use umjunsik::ast::{Expr, Program, Statement};
+use umjunsik::codegen::CodeGenerator;
+use umjunsik::{CompileError, compile_umjunsik, generate_c, generate_llvm_ir, generate_wat, parse, validate};
+
+fn errors(source: &str) -> Vec<String> {
+    let program = parse(source).unwrap_or_else(|err| panic!("failed to parse: {}", err));
+    validate(&program).expect_err("program should fail validation").iter().map(CompileError::to_string).collect()
+}
+
+#[test]
+fn valid_program() {
+    let program = parse("어떻게\n엄...\n동탄어?식어!\n준..\n이 사람이름이냐ㅋㅋ").unwrap();
+    assert_eq!(validate(&program), Ok(()));
+}
+
+#[test]
+fn missing_goto_target() {
+    assert_eq!(
+        errors("어떻게\n식.!\n준@9\n이 사람이름이냐ㅋㅋ"),
+        ["goto target line 9 does not exist at line 3, col 1"]
+    );
+}
+
+#[test]
+fn goto_line_zero_or_below() {
+    // The parser rejects a constant target below 1 before any backend sees it
+    for (target, value) in [("준,", -1), ("준.,", 0), ("준@0", 0)] {
+        let source = format!("어떻게\n식.!\n{}\n이 사람이름이냐ㅋㅋ", target);
+        let err = parse(&source).unwrap_err();
+        assert_eq!(err.to_string(), format!("Goto line number must be positive, got {}", value));
+        assert_eq!(err.location().map(|(line, _)| line), Some(3), "{}", target);
+    }
+
+    // A hand-built goto to line 0 fails the same check in validate and every backend
+    let program = Program {
+        statements: vec![(Statement::Goto { target: 0, line: 2 }, 2)],
+        spans: Vec::new(),
+        locals: BTreeMap::new(),
+        missing_end_marker: None,
+    };
+    let expected = "goto target line 0 does not exist at line 2, col 1";
+    let errors: Vec<String> = validate(&program).unwrap_err().iter().map(CompileError::to_string).collect();
+    assert_eq!(errors, [expected]);
+    let results = [
+        CodeGenerator::new(false).generate(&program),
+        generate_c(&program),
+        generate_wat(&program),
+        generate_llvm_ir(&program),
+    ];
+    for result in results {
+        assert_eq!(result.unwrap_err().to_string(), expected);
+    }
+}
+
+#[test]
+fn variable_index_out_of_range() {
+    let program = Program {
+        statements: vec![(
+            Statement::Assign {
+                var_index: 0,
+                value: Expr::Var(10001),
+            },
+            2,
+        )],
+        spans: Vec::new(),
+        locals: BTreeMap::new(),
+        missing_end_marker: None,
+    };
+    let errors: Vec<String> = validate(&program).unwrap_err().iter().map(CompileError::to_string).collect();
+    assert_eq!(
+        errors,
+        [
+            "variable index 0 is outside 1 to 10000 at line 2, col 1",
+            "variable index 10001 is outside 1 to 10000 at line 2, col 1",
+        ]
+    );
+}
+
+#[test]
+fn empty_conditional() {
+    assert_eq!(
+        errors("어떻게\n엄.\n동탄어?\n;\n이 사람이름이냐ㅋㅋ"),
+        ["conditional has neither a body nor an else part at line 3, col 1"]
+    );
+}
+
+#[test]
+fn constant_modulo_by_zero() {
+    assert_eq!(
+        errors("어떻게\n엄.\n식어%(.,)!\n이 사람이름이냐ㅋㅋ"),
+        ["modulo by constant zero at line 3, col 1"]
+    );
+}
+
+#[test]
+fn errors_in_source_order() {
+    assert_eq!(
+        errors("어떻게\n엄.%@0\n준@9\n이 사람이름이냐ㅋㅋ"),
+        [
+            "modulo by constant zero at line 2, col 1",
+            "goto target line 9 does not exist at line 3, col 1",
+        ]
+    );
+}
+
+#[test]
+fn compile_reports_first_error() {
+    let err = compile_umjunsik("어떻게\n엄.%@0\n준@9\n이 사람이름이냐ㅋㅋ").unwrap_err();
+    assert_eq!(err.to_string(), "modulo by constant zero at line 2, col 1");
+}
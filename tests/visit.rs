@@ -0,0 +1,54 @@
+//! A `Visitor` written outside the crate, as an analysis pass would be.
+
+use umjunsik::ast::{Expr, Statement};
+use umjunsik::{Visitor, parse};
+
+// Counts `식...!` statements, including those inside conditional and loop bodies
+#[derive(Default)]
+struct PrintNumCounter {
+    count: usize,
+}
+
+impl Visitor for PrintNumCounter {
+    fn visit_statement(&mut self, stmt: &Statement) {
+        if matches!(stmt, Statement::PrintNum(_)) {
+            self.count += 1;
+        }
+        self.walk_statement(stmt);
+    }
+}
+
+// Counts variable reads, which only the default walk reaches
+#[derive(Default)]
+struct VarReadCounter {
+    count: usize,
+}
+
+impl Visitor for VarReadCounter {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if matches!(expr, Expr::Var(_)) {
+            self.count += 1;
+        }
+        self.walk_expr(expr);
+    }
+}
+
+// Prints on lines 3, 4 (the then part; the else part prints a character) and 6 (the loop body)
+const NESTED: &str = "어떻게\n엄...\n식어!\n동탄어?식.!:식..ㅋ\n엄.....\n동탄어=@0??식어!~엄어,\n이 사람이름이냐ㅋㅋ";
+
+#[test]
+fn counts_print_num_statements_at_every_depth() {
+    let program = parse(NESTED).unwrap();
+    let mut counter = PrintNumCounter::default();
+    counter.visit_program(&program);
+    assert_eq!(counter.count, 3);
+}
+
+#[test]
+fn default_walk_reaches_every_expression() {
+    let program = parse(NESTED).unwrap();
+    let mut counter = VarReadCounter::default();
+    counter.visit_program(&program);
+    // 식어!, the 동탄 condition, the loop condition, its 식어! and the 어 in 엄어,
+    assert_eq!(counter.count, 5);
+}